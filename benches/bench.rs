@@ -1,6 +1,6 @@
 use std::ops::Range;
 
-use criterion::{Criterion, criterion_main, criterion_group};
+use criterion::{BenchmarkId, Criterion, criterion_main, criterion_group};
 use nclist::NClist;
 use rand::Rng;
 
@@ -36,9 +36,25 @@ fn test_nc_overlaps(c:  &mut Criterion) {
 
     let q = make_ranges(100,10,50,10_000);
 
-    c.bench_function_over_inputs("NClist vs fraction overlap", move |b, &&i| b.iter(|| query_nclist(&nclists[i], &q)),
-        &[0usize,1,2,3,4,5,6,7,8,9]);
+    let mut group = c.benchmark_group("NClist vs fraction overlap");
+    for (i, nclist) in nclists.iter().enumerate() {
+        group.bench_with_input(BenchmarkId::from_parameter(i), nclist, |b, nclist| b.iter(|| query_nclist(nclist, &q)));
+    }
+    group.finish();
 }   
-criterion_group!(benches, test_nc, test_nc_overlaps);
+fn test_nc_batch(c: &mut Criterion) {
+    let nclist = NClist::from_vec(make_ranges(1_000_000, 10, 100, 10_000_000)).unwrap();
+    let mut q = make_ranges(1_000_000, 1, 10, 10_000_000);
+    q.sort_by_key(|r| r.start);
+
+    let mut group = c.benchmark_group("NClist sorted queries");
+    group.bench_function("count_overlaps", |b| b.iter(|| q.iter().map(|r| nclist.count_overlaps(r)).sum::<usize>()));
+    group.bench_function("count_overlaps_many", |b| b.iter(|| nclist.count_overlaps_many(&q).sum::<usize>()));
+    group.bench_function("overlaps", |b| b.iter(|| q.iter().map(|r| nclist.overlaps(r).collect::<Vec<_>>().len()).sum::<usize>()));
+    group.bench_function("overlaps_many", |b| b.iter(|| nclist.overlaps_many(&q).map(|v| v.len()).sum::<usize>()));
+    group.finish();
+}
+
+criterion_group!(benches, test_nc, test_nc_overlaps, test_nc_batch);
 criterion_main!(benches);
 
@@ -0,0 +1,402 @@
+//! Zero-copy archives of an `NClist<T>`.
+//!
+//! An archive stores the flattened `intervals` and `contained` vectors of an `NClist` in their
+//! in-memory representation. An `ArchivedNClist` is a view on such a byte buffer that is queried
+//! directly, without decoding or copying the intervals. Unlike the `disk` format, the archive
+//! uses the native byte order and requires a buffer aligned to 8 bytes, for example a
+//! `disk::Mmap` (with the `disk` feature) or an `AlignedBuffer`.
+//!
+//! The layout of an archive is (all numbers in native byte order):
+//!
+//! | bytes             | content                                                  |
+//! |-------------------|----------------------------------------------------------|
+//! | 8                 | magic `NCLARCH\x01`                                      |
+//! | 8                 | byte order marker `0x0102030405060708` as `u64`          |
+//! | 8                 | size of a single archived interval as `u64`              |
+//! | 8                 | number of intervals `n` as `u64`                         |
+//! | 16 * (n + 1)      | sublist `(start, end)` pairs, `u64::MAX` for no sublist  |
+//! | size * n          | archived intervals                                       |
+use std::io::{self, Write};
+use std::mem;
+use std::ops::{Bound, Deref, Range};
+
+use crate::{count_ending_before, layout, query, Interval, Layout, NClist, OrderedState, OverlapsState, Query};
+
+const MAGIC: &[u8; 8] = b"NCLARCH\x01";
+const BYTE_ORDER: u64 = 0x0102_0304_0506_0708;
+const HEADER_SIZE: usize = 32;
+const NO_SUBLIST: u64 = u64::MAX;
+const ALIGN: usize = 8;
+
+/// Interval types that can be used directly from the bytes of an archive.
+///
+/// # Safety
+/// Implementors must have a defined layout (`#[repr(C)]` or `#[repr(transparent)]`) without
+/// padding bytes, must not contain pointers or references, must be valid for every bit pattern
+/// and must have an alignment of at most 8 bytes.
+pub unsafe trait Plain: Interval + Copy {}
+
+/// Interval types that can be stored in an archive as `Archived`.
+///
+/// The archived intervals are queried with the `SEMANTICS` of `Archived`, which must be the same
+/// as the `SEMANTICS` of the interval type. Writing or opening an archive panics otherwise.
+pub trait Archive: Interval {
+    /// The representation of the interval in the archive.
+    type Archived: Plain<Coord = Self::Coord>;
+
+    /// Convert the interval to its archived representation.
+    fn archive(&self) -> Self::Archived;
+}
+
+/// The archived representation of a `Range`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ArchivedRange<C> {
+    pub start: C,
+    pub end: C,
+}
+
+impl<C> Interval for ArchivedRange<C> where C: Ord {
+    type Coord = C;
+
+    #[inline(always)]
+    fn start(&self) -> &C {
+        &self.start
+    }
+
+    #[inline(always)]
+    fn end(&self) -> &C {
+        &self.end
+    }
+}
+
+macro_rules! impl_archive_range {
+    ($($t:ty),*) => {$(
+        // SAFETY: two integers of the same type in a `#[repr(C)]` struct have no padding and
+        // every bit pattern is valid.
+        unsafe impl Plain for ArchivedRange<$t> {}
+
+        impl Archive for Range<$t> {
+            type Archived = ArchivedRange<$t>;
+
+            fn archive(&self) -> ArchivedRange<$t> {
+                ArchivedRange { start: self.start, end: self.end }
+            }
+        }
+    )*}
+}
+
+impl_archive_range!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+/// A byte buffer aligned to 8 bytes, as required by `ArchivedNClist::from_bytes`.
+#[derive(Debug, Clone, Default)]
+pub struct AlignedBuffer {
+    words: Vec<u64>,
+    len: usize,
+}
+
+impl AlignedBuffer {
+    /// Copy `bytes` to a new aligned buffer.
+    pub fn from_bytes(bytes: &[u8]) -> AlignedBuffer {
+        let mut buf = AlignedBuffer::default();
+        buf.extend_from_slice(bytes);
+        buf
+    }
+
+    #[allow(clippy::manual_div_ceil)]
+    fn extend_from_slice(&mut self, bytes: &[u8]) {
+        let len = self.len + bytes.len();
+        self.words.resize((len + ALIGN - 1) / ALIGN, 0);
+        // SAFETY: the words provide at least `len` initialized bytes.
+        let all = unsafe { std::slice::from_raw_parts_mut(self.words.as_mut_ptr() as *mut u8, len) };
+        all[self.len..].copy_from_slice(bytes);
+        self.len = len;
+    }
+}
+
+impl Deref for AlignedBuffer {
+    type Target = [u8];
+    fn deref(&self) -> &[u8] {
+        // SAFETY: the words provide at least `len` initialized bytes.
+        unsafe { std::slice::from_raw_parts(self.words.as_ptr() as *const u8, self.len) }
+    }
+}
+
+impl AsRef<[u8]> for AlignedBuffer {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+impl Write for AlignedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl<T> NClist<T> where T: Archive {
+    /// Write the `NClist` in the archive format to `w`. The archive can be used with
+    /// `ArchivedNClist::from_bytes` on a machine with the same byte order.
+    pub fn write_archive<W: Write>(&self, mut w: W) -> io::Result<()> {
+        assert_same_semantics::<T>();
+        w.write_all(MAGIC)?;
+        w.write_all(&BYTE_ORDER.to_ne_bytes())?;
+        w.write_all(&(mem::size_of::<T::Archived>() as u64).to_ne_bytes())?;
+        w.write_all(&(self.intervals.len() as u64).to_ne_bytes())?;
+        for c in &self.contained {
+            let (start, end) = c.map_or((NO_SUBLIST, NO_SUBLIST), |(s, e)| (s as u64, e as u64));
+            w.write_all(&start.to_ne_bytes())?;
+            w.write_all(&end.to_ne_bytes())?;
+        }
+        for e in &self.intervals {
+            let archived = e.archive();
+            // SAFETY: `Plain` types have no padding, so all bytes of `archived` are initialized.
+            let bytes = unsafe {
+                std::slice::from_raw_parts(&archived as *const T::Archived as *const u8, mem::size_of::<T::Archived>())
+            };
+            w.write_all(bytes)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the archive of this `NClist` in an aligned buffer.
+    pub fn to_archive(&self) -> AlignedBuffer {
+        let mut buf = AlignedBuffer::default();
+        self.write_archive(&mut buf).expect("writing to memory cannot fail");
+        buf
+    }
+}
+
+/// A view on an archived `NClist<T>` in a byte buffer. The intervals are returned as references
+/// to `T::Archived` in the buffer.
+#[derive(Debug)]
+pub struct ArchivedNClist<'a, T> where T: Archive {
+    intervals: &'a [T::Archived],
+    contained: &'a [[u64; 2]],
+}
+
+impl<'a, T> Clone for ArchivedNClist<'a, T> where T: Archive {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'a, T> Copy for ArchivedNClist<'a, T> where T: Archive {}
+
+/// Iterator over the elements overlapping a query, created by `ArchivedNClist::overlaps`.
+pub struct ArchivedOverlaps<'a, T> where T: Archive {
+    nclist: ArchivedNClist<'a, T>,
+    start: Option<&'a T::Coord>,
+    end: Bound<&'a T::Coord>,
+    state: OverlapsState,
+}
+
+/// Iterator over the elements overlapping a query ordered by start coordinate, created by
+/// `ArchivedNClist::overlaps_ordered`.
+pub struct ArchivedOrderedOverlaps<'a, T> where T: Archive {
+    nclist: ArchivedNClist<'a, T>,
+    start: Option<&'a T::Coord>,
+    end: Bound<&'a T::Coord>,
+    state: OrderedState,
+}
+
+impl<'a, T> ArchivedNClist<'a, T> where T: Archive {
+    /// Use `bytes`, created with `NClist::write_archive`, as an `NClist<T>`. The header, size
+    /// and alignment of the buffer and the nesting of the intervals are validated, the intervals
+    /// are not copied.
+    ///
+    /// Validating the nesting visits every interval once. Use `from_bytes_unchecked` to open
+    /// large archives from a trusted source in constant time.
+    pub fn from_bytes(bytes: &'a [u8]) -> io::Result<ArchivedNClist<'a, T>> {
+        let nclist = ArchivedNClist::from_bytes_unchecked(bytes)?;
+        layout::check(&nclist, nclist.intervals, nclist.contained.len())
+            .map_err(|msg| io::Error::new(io::ErrorKind::InvalidData, msg))?;
+        Ok(nclist)
+    }
+
+    /// Use `bytes`, created with `NClist::write_archive`, as an `NClist<T>` without validating
+    /// the nesting of the intervals. Only the header, size and alignment of the buffer are
+    /// checked. Queries on an archive with an invalid nesting return wrong results or panic.
+    pub fn from_bytes_unchecked(bytes: &'a [u8]) -> io::Result<ArchivedNClist<'a, T>> {
+        assert_same_semantics::<T>();
+        let invalid = |msg| io::Error::new(io::ErrorKind::InvalidData, msg);
+        if bytes.len() < HEADER_SIZE || &bytes[..8] != MAGIC {
+            return Err(invalid("Not an NClist archive"));
+        }
+        if read_u64(bytes, 8) != BYTE_ORDER {
+            return Err(invalid("NClist archive was created with a different byte order"));
+        }
+        if read_u64(bytes, 16) != mem::size_of::<T::Archived>() as u64 {
+            return Err(invalid("NClist archive contains a different interval type"));
+        }
+        if mem::align_of::<T::Archived>() > ALIGN || (bytes.as_ptr() as usize) & (ALIGN - 1) != 0 {
+            return Err(invalid("NClist archive is not aligned to 8 bytes"));
+        }
+        let len = read_u64(bytes, 24) as usize;
+        let expected = len.checked_add(1).and_then(|n| n.checked_mul(16))
+            .and_then(|c| len.checked_mul(mem::size_of::<T::Archived>()).and_then(|i| i.checked_add(c)))
+            .and_then(|s| s.checked_add(HEADER_SIZE));
+        if expected != Some(bytes.len()) {
+            return Err(invalid("NClist archive has an unexpected size"));
+        }
+
+        let intervals_offset = HEADER_SIZE + 16 * (len + 1);
+        // SAFETY: the size has been checked and the buffer and both offsets are aligned to 8
+        // bytes. `u64` and `Plain` types are valid for every bit pattern.
+        unsafe {
+            Ok(ArchivedNClist {
+                contained: std::slice::from_raw_parts(bytes[HEADER_SIZE..].as_ptr() as *const [u64; 2], len + 1),
+                intervals: std::slice::from_raw_parts(bytes[intervals_offset..].as_ptr() as *const T::Archived, len),
+            })
+        }
+    }
+
+    /// Returns the number of intervals.
+    pub fn len(&self) -> usize {
+        self.intervals.len()
+    }
+
+    /// Returns `true` if the list contains no intervals.
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    /// Count the number of elements overlapping query `q`.
+    pub fn count_overlaps<Q>(&self, q: &Q) -> usize where Q: Query<T::Coord> + ?Sized {
+        self.overlaps(q).count()
+    }
+
+    /// Count the number of elements that contain coordinate `p` according to their `SEMANTICS`,
+    /// e.g. `start <= p < end` for half-open elements.
+    pub fn count_stab(&self, p: &T::Coord) -> usize {
+        self.stab(p).count()
+    }
+
+    /// Returns an iterator that returns overlapping elements to query `q`.
+    pub fn overlaps<'q, Q>(&self, q: &'q Q) -> ArchivedOverlaps<'q, T> where 'a: 'q, Q: Query<T::Coord> + ?Sized {
+        self.overlaps_between(q.lower(), q.upper())
+    }
+
+    /// Returns an iterator that returns the elements that contain coordinate `p`.
+    pub fn stab<'q>(&self, p: &'q T::Coord) -> ArchivedOverlaps<'q, T> where 'a: 'q {
+        self.overlaps_between(Some(p), Bound::Included(p))
+    }
+
+    /// Returns an iterator that returns overlapping elements to query `q` ordered by start
+    /// coordinate.
+    pub fn overlaps_ordered<'q, Q>(&self, q: &'q Q) -> ArchivedOrderedOverlaps<'q, T> where 'a: 'q, Q: Query<T::Coord> + ?Sized {
+        let (start, end) = (q.lower(), q.upper());
+        ArchivedOrderedOverlaps { nclist: *self, start, end, state: OrderedState::new(self, start, end) }
+    }
+
+    fn overlaps_between<'q>(&self, start: Option<&'q T::Coord>, end: Bound<&'q T::Coord>) -> ArchivedOverlaps<'q, T> where 'a: 'q {
+        ArchivedOverlaps { nclist: *self, start, end, state: OverlapsState::new(self, start, end) }
+    }
+}
+
+impl<'a, T> Layout for ArchivedNClist<'a, T> where T: Archive {
+    type Item = T::Archived;
+
+    #[inline(always)]
+    fn starts_before(&self, i: usize, q_end: Bound<&T::Coord>) -> bool {
+        query::starts_before(self.intervals[i].start(), q_end)
+    }
+
+    #[inline(always)]
+    fn sublist(&self, i: usize) -> Option<(usize, usize)> {
+        let [start, end] = self.contained[i];
+        if start == NO_SUBLIST {
+            None
+        } else {
+            Some((start as usize, end as usize))
+        }
+    }
+
+    #[inline]
+    fn skip_ending_before(&self, start: usize, end: usize, q: Option<&T::Coord>) -> usize {
+        q.map_or(0, |q| count_ending_before(&self.intervals[start..end], q))
+    }
+}
+
+impl<'a, T> Iterator for ArchivedOverlaps<'a, T> where T: Archive {
+    type Item = &'a T::Archived;
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let nclist = self.nclist;
+        self.state.next_index(&nclist, self.start, self.end).map(|i| &nclist.intervals[i])
+    }
+}
+
+impl<'a, T> Iterator for ArchivedOrderedOverlaps<'a, T> where T: Archive {
+    type Item = &'a T::Archived;
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let nclist = self.nclist;
+        self.state.next_index(&nclist, self.start, self.end).map(|i| &nclist.intervals[i])
+    }
+}
+
+/// Panics if the archived intervals of `T` are interpreted differently than `T`.
+#[inline]
+fn assert_same_semantics<T: Archive>() {
+    assert!(T::SEMANTICS == <T::Archived as Interval>::SEMANTICS, "Archived intervals have different semantics");
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    let mut b = [0; 8];
+    b.copy_from_slice(&bytes[offset..offset + 8]);
+    u64::from_ne_bytes(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::random_intervals;
+
+    #[test]
+    fn archive() {
+        let list = random_intervals(100, 50, 13);
+        let nclist = NClist::from_vec(list).unwrap();
+        let buf = nclist.to_archive();
+        let archived: ArchivedNClist<Range<u32>> = ArchivedNClist::from_bytes(&buf).unwrap();
+        assert_eq!(archived.len(), 100);
+        for q in (0..70).map(|s| s..s + 1 + s % 7) {
+            assert_eq!(archived.count_overlaps(&q), nclist.count_overlaps(&q));
+            let found: Vec<_> = archived.overlaps_ordered(&q).map(|r| r.start..r.end).collect();
+            let expected: Vec<_> = nclist.overlaps_ordered(&q).cloned().collect();
+            assert_eq!(found, expected);
+            assert_eq!(archived.count_stab(&q.start), nclist.count_stab(&q.start));
+        }
+        assert_eq!(archived.overlaps(&(20..)).count(), nclist.overlaps(&(20..)).count());
+
+        let empty: NClist<Range<i64>> = NClist::from_vec(Vec::new()).unwrap();
+        let buf = empty.to_archive();
+        let archived = ArchivedNClist::<Range<i64>>::from_bytes(&buf).unwrap();
+        assert!(archived.is_empty());
+        assert_eq!(archived.count_overlaps(&(0..10)), 0);
+    }
+
+    #[test]
+    fn invalid_archive() {
+        let nclist = NClist::from_vec(vec![(10u32..15), (10..20), (1..8)]).unwrap();
+        let buf = nclist.to_archive();
+        assert!(ArchivedNClist::<Range<u64>>::from_bytes(&buf).is_err());
+        assert!(ArchivedNClist::<Range<u32>>::from_bytes(&buf[..buf.len() - 1]).is_err());
+        assert!(ArchivedNClist::<Range<u32>>::from_bytes(&AlignedBuffer::from_bytes(&buf[8..])).is_err());
+        assert!(ArchivedNClist::<Range<u32>>::from_bytes(&buf).is_ok());
+
+        //a sublist that points outside the list
+        let mut bytes = buf.to_vec();
+        bytes[HEADER_SIZE + 16..HEADER_SIZE + 24].copy_from_slice(&7u64.to_ne_bytes());
+        bytes[HEADER_SIZE + 24..HEADER_SIZE + 32].copy_from_slice(&9u64.to_ne_bytes());
+        let bytes = AlignedBuffer::from_bytes(&bytes);
+        assert!(ArchivedNClist::<Range<u32>>::from_bytes(&bytes).is_err());
+        assert!(ArchivedNClist::<Range<u32>>::from_bytes_unchecked(&bytes).is_ok());
+        assert!(ArchivedNClist::<Range<u32>>::from_bytes_unchecked(&buf[..buf.len() - 1]).is_err());
+    }
+}
@@ -0,0 +1,179 @@
+//! Batch queries on an `NClist<T>`.
+//!
+//! When many queries are sorted by start coordinate the first overlapping element in a sublist can
+//! only move forward. A `Sweep` remembers this position for every visited sublist and advances it
+//! from the previous query instead of starting a new binary search. Queries that are not sorted
+//! are still answered correctly, a position that has moved past the query is searched again in
+//! the part of the sublist before it.
+use std::borrow::Borrow;
+use std::collections::VecDeque;
+use std::ops::{Bound, Range};
+
+use crate::query::{self, Query};
+use crate::{Interval, NClist};
+
+/// The per-sublist positions for a series of queries with increasing start coordinates.
+pub(crate) struct Sweep<'a, T> where T: 'a + Interval {
+    nclist: &'a NClist<T>,
+    //the position in every sublist, indexed like `contained`
+    cursors: Vec<usize>,
+    queue: VecDeque<usize>,
+}
+
+/// Iterator returning the overlapping elements for every query, created by
+/// `NClist::overlaps_many`.
+pub struct OverlapsMany<'a, T, I> where T: 'a + Interval {
+    sweep: Sweep<'a, T>,
+    queries: I,
+}
+
+/// Iterator returning the number of overlapping elements for every query, created by
+/// `NClist::count_overlaps_many`.
+pub struct CountOverlapsMany<'a, T, I> where T: 'a + Interval {
+    sweep: Sweep<'a, T>,
+    queries: I,
+}
+
+impl<T> NClist<T> where T: Interval {
+    /// Returns an iterator that returns a `Vec` with the overlapping elements for every query in
+    /// `queries`, in the order of the queries. This is faster than calling `overlaps` for every
+    /// query when the queries are sorted by start coordinate.
+    pub fn overlaps_many<'a, I>(&'a self, queries: I) -> OverlapsMany<'a, T, I::IntoIter>
+        where I: IntoIterator, I::Item: Borrow<Range<T::Coord>>
+    {
+        OverlapsMany { sweep: Sweep::new(self), queries: queries.into_iter() }
+    }
+
+    /// Returns an iterator that returns the number of overlapping elements for every query in
+    /// `queries`, in the order of the queries. This is faster than calling `count_overlaps` for
+    /// every query when the queries are sorted by start coordinate.
+    pub fn count_overlaps_many<'a, I>(&'a self, queries: I) -> CountOverlapsMany<'a, T, I::IntoIter>
+        where I: IntoIterator, I::Item: Borrow<Range<T::Coord>>
+    {
+        CountOverlapsMany { sweep: Sweep::new(self), queries: queries.into_iter() }
+    }
+}
+
+impl<'a, T> Sweep<'a, T> where T: Interval {
+    pub(crate) fn new(nclist: &'a NClist<T>) -> Sweep<'a, T> {
+        let cursors = nclist.contained.iter().map(|c| c.map_or(0, |(start, _)| start)).collect();
+        Sweep { nclist, cursors, queue: VecDeque::new() }
+    }
+
+    /// Call `f` for every element overlapping the query from `q` to `q_end` (see `Query`).
+    /// Queries with a start coordinate that is larger than or equal to the previous query
+    /// continue from the stored sublist positions.
+    pub(crate) fn query<F>(&mut self, q: Option<&T::Coord>, q_end: Bound<&T::Coord>, mut f: F) where F: FnMut(&'a T) {
+        if query::is_empty(q, q_end) {
+            return;
+        }
+        let nclist = self.nclist;
+
+        self.queue.push_back(0);
+        while let Some(slot) = self.queue.pop_front() {
+            let (start, end) = nclist.contained[slot].unwrap();
+            let cursor = &mut self.cursors[slot];
+            *cursor = match q {
+                None => start,
+                //the query starts before the previous query
+                Some(q) if *cursor > start && !query::ends_before(&nclist.intervals[*cursor - 1], q) => {
+                    start + nclist.bin_search_end(start, *cursor, q)
+                },
+                Some(q) => gallop_end(&nclist.intervals, *cursor, end, q),
+            };
+
+            let mut pos = *cursor;
+            while pos < end && query::starts_before(nclist.intervals[pos].start(), q_end) {
+                f(&nclist.intervals[pos]);
+                pos += 1;
+                if nclist.contained[pos].is_some() {
+                    self.queue.push_back(pos);
+                }
+            }
+        }
+    }
+}
+
+/// Returns the first position in `pos..end` with an end coordinate larger than `q`, by
+/// exponentially increasing the step size from `pos` and finishing with a binary search.
+fn gallop_end<T: Interval>(intervals: &[T], pos: usize, end: usize, q: &T::Coord) -> usize {
+    let mut lo = pos;
+    let mut step = 1;
+    while lo + step < end && query::ends_before(&intervals[lo + step - 1], q) {
+        lo += step;
+        step *= 2;
+    }
+    let hi = (lo + step).min(end);
+    lo + intervals[lo..hi].partition_point(|e| query::ends_before(e, q))
+}
+
+impl<'a, T, I> Iterator for OverlapsMany<'a, T, I>
+    where T: Interval, I: Iterator, I::Item: Borrow<Range<T::Coord>>
+{
+    type Item = Vec<&'a T>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let q = self.queries.next()?;
+        let q = q.borrow();
+        let mut result = Vec::new();
+        self.sweep.query(q.lower(), q.upper(), |e| result.push(e));
+        Some(result)
+    }
+}
+
+impl<'a, T, I> Iterator for CountOverlapsMany<'a, T, I>
+    where T: Interval, I: Iterator, I::Item: Borrow<Range<T::Coord>>
+{
+    type Item = usize;
+    fn next(&mut self) -> Option<Self::Item> {
+        let q = self.queries.next()?;
+        let q = q.borrow();
+        let mut count = 0;
+        self.sweep.query(q.lower(), q.upper(), |_| count += 1);
+        Some(count)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{random_intervals, Site};
+    use crate::NClist;
+    use std::ops::Range;
+
+    #[test]
+    fn sorted_queries() {
+        let v = random_intervals(500, 1000, 60);
+        let nclist = NClist::from_vec(v).unwrap();
+        let queries: Vec<Range<u32>> = (0..1100).step_by(7).map(|s| s..s + 1 + s % 30).collect();
+
+        let counts: Vec<usize> = nclist.count_overlaps_many(&queries).collect();
+        let results: Vec<_> = nclist.overlaps_many(queries.iter().cloned()).collect();
+        assert_eq!(results.len(), queries.len());
+        for ((q, count), result) in queries.iter().zip(counts).zip(results) {
+            assert_eq!(count, nclist.count_overlaps(q));
+            assert!(result.into_iter().eq(nclist.overlaps(q)));
+        }
+    }
+
+    #[test]
+    fn unsorted_queries() {
+        let nclist = NClist::from_vec(vec![(10..15), (10..20), (1..8), (12..13)]).unwrap();
+        let queries = vec![(12..14), (1..2), (15..30), (11..12), (8..8), (0..100)];
+        let counts: Vec<usize> = nclist.count_overlaps_many(&queries).collect();
+        assert_eq!(counts, vec![3, 1, 1, 2, 0, 4]);
+    }
+
+    #[test]
+    fn closed_and_empty_elements() {
+        let closed = NClist::from_vec(vec![(1u32..=5), (5..=8), (9..=9)]).unwrap();
+        let queries = vec![(0..1), (0..2), (5..6), (8..9), (9..10), (10..20)];
+        let counts: Vec<usize> = closed.count_overlaps_many(&queries).collect();
+        assert_eq!(counts, vec![0, 1, 2, 1, 1, 0]);
+        for (q, result) in queries.iter().zip(closed.overlaps_many(&queries)) {
+            assert!(result.into_iter().eq(closed.overlaps(q)));
+        }
+
+        let sites = NClist::from_vec(vec![Site(5..5), Site(3..8), Site(8..8)]).unwrap();
+        let counts: Vec<usize> = sites.count_overlaps_many(&[(0..5), (4..6), (5..6), (8..9)]).collect();
+        assert_eq!(counts, vec![1, 2, 2, 1]);
+    }
+}
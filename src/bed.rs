@@ -0,0 +1,272 @@
+//! Reader for BED files.
+//!
+//! The reader supports BED3 up to BED12 records. Fields are separated by tabs, lines without a tab
+//! are split on whitespace. Empty lines, comments (`#`) and `track` and `browser` lines are
+//! skipped. BED coordinates are 0-based and half-open, so a `BedRecord` can directly be used as an
+//! `Interval` with the same semantics as `std::ops::Range`.
+//!
+//! # Example
+//! ```
+//! use nclist::GenomeNClist;
+//! use nclist::bed::Reader;
+//!
+//! let data = "track name=example\nchr1\t10\t20\tgene1\nchr1\t15\t18\tgene2\nchr2\t5\t10\tgene3\n";
+//! let records = Reader::new(data.as_bytes()).collect::<Result<Vec<_>, _>>().unwrap();
+//! let genome = GenomeNClist::from_vec_by_key(records, |r| r.chrom.clone()).unwrap();
+//! assert_eq!(genome.count_overlaps("chr1", &(16..17)), Ok(2));
+//! ```
+use std::error::Error as StdError;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::ops::Range;
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::{Interval, Strand, Stranded};
+
+/// A single BED record. Optional fields are `None` when the column is not present or contains
+/// `.`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BedRecord {
+    pub chrom: String,
+    pub start: u64,
+    pub end: u64,
+    pub name: Option<String>,
+    pub score: Option<f64>,
+    pub strand: Strand,
+    pub thick_start: Option<u64>,
+    pub thick_end: Option<u64>,
+    pub item_rgb: Option<String>,
+    /// The blocks (exons) of a BED12 record as absolute coordinates.
+    pub blocks: Option<Vec<Range<u64>>>,
+}
+
+/// Errors that can occur while reading a BED file.
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "I/O error reading BED: {}", e),
+            Error::Parse { line, message } => write!(f, "Invalid BED record on line {}: {}", line, message),
+        }
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Parse { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error::Io(e)
+    }
+}
+
+impl Interval for BedRecord {
+    type Coord = u64;
+
+    #[inline(always)]
+    fn start(&self) -> &u64 {
+        &self.start
+    }
+
+    #[inline(always)]
+    fn end(&self) -> &u64 {
+        &self.end
+    }
+}
+
+impl Stranded for BedRecord {
+    #[inline(always)]
+    fn strand(&self) -> Strand {
+        self.strand
+    }
+}
+
+/// An iterator over the records in a BED file.
+pub struct Reader<R> {
+    inner: R,
+    line: usize,
+    buf: String,
+}
+
+impl Reader<BufReader<File>> {
+    /// Open the BED file at `path`.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> io::Result<Reader<BufReader<File>>> {
+        File::open(path).map(|f| Reader::new(BufReader::new(f)))
+    }
+}
+
+impl<R> Reader<R> where R: BufRead {
+    /// Create a reader that reads BED records from `inner`.
+    pub fn new(inner: R) -> Reader<R> {
+        Reader { inner, line: 0, buf: String::new() }
+    }
+}
+
+impl<R> Iterator for Reader<R> where R: BufRead {
+    type Item = Result<BedRecord, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.buf.clear();
+            match self.inner.read_line(&mut self.buf) {
+                Ok(0) => return None,
+                Ok(_) => self.line += 1,
+                Err(e) => return Some(Err(e.into())),
+            }
+            let line = self.buf.trim_end_matches(&['\n', '\r'][..]);
+            if line.trim().is_empty() || line.starts_with('#') || is_header(line, "track") || is_header(line, "browser") {
+                continue;
+            }
+            return Some(parse_record(line).map_err(|message| Error::Parse { line: self.line, message }));
+        }
+    }
+}
+
+/// Returns `true` if `line` is a header line starting with the word `keyword`.
+fn is_header(line: &str, keyword: &str) -> bool {
+    matches!(line.strip_prefix(keyword), Some(rest) if rest.is_empty() || rest.starts_with(char::is_whitespace))
+}
+
+fn parse_record(line: &str) -> Result<BedRecord, String> {
+    let fields: Vec<&str> = if line.contains('\t') {
+        line.split('\t').collect()
+    } else {
+        line.split_whitespace().collect()
+    };
+    if fields.len() < 3 || fields.len() > 12 {
+        return Err(format!("expected 3 to 12 columns, found {}", fields.len()));
+    }
+
+    let start: u64 = parse_field(fields[1], "chromStart")?;
+    let end: u64 = parse_field(fields[2], "chromEnd")?;
+    if end < start {
+        return Err(format!("chromEnd {} is smaller than chromStart {}", end, start));
+    }
+
+    let optional = |i: usize| fields.get(i).copied().filter(|f| *f != ".");
+    let strand = match optional(5) {
+        None => Strand::Unknown,
+        Some("+") => Strand::Forward,
+        Some("-") => Strand::Reverse,
+        Some(s) => return Err(format!("invalid strand '{}'", s)),
+    };
+
+    let blocks = if fields.len() > 9 {
+        if fields.len() != 12 {
+            return Err("BED12 block fields are incomplete".to_string());
+        }
+        let count: usize = parse_field(fields[9], "blockCount")?;
+        let sizes = parse_list(fields[10], "blockSizes")?;
+        let starts = parse_list(fields[11], "blockStarts")?;
+        if sizes.len() != count || starts.len() != count {
+            return Err(format!("expected {} blockSizes and blockStarts", count));
+        }
+        let blocks = starts.iter().zip(sizes).map(|(&s, size)| {
+            start.checked_add(s)
+                .and_then(|block_start| block_start.checked_add(size).map(|block_end| block_start..block_end))
+                .filter(|block| block.end <= end)
+                .ok_or_else(|| format!("block at {} with size {} is outside the record", s, size))
+        });
+        Some(blocks.collect::<Result<_, _>>()?)
+    } else {
+        None
+    };
+
+    let thick_start: Option<u64> = optional(6).map(|s| parse_field(s, "thickStart")).transpose()?;
+    let thick_end: Option<u64> = optional(7).map(|s| parse_field(s, "thickEnd")).transpose()?;
+    if thick_start.iter().chain(&thick_end).any(|&t| t < start || t > end) {
+        return Err("thickStart and thickEnd must be inside the record".to_string());
+    }
+    if let (Some(thick_start), Some(thick_end)) = (thick_start, thick_end) {
+        if thick_end < thick_start {
+            return Err(format!("thickEnd {} is smaller than thickStart {}", thick_end, thick_start));
+        }
+    }
+
+    Ok(BedRecord {
+        chrom: fields[0].to_string(),
+        start,
+        end,
+        name: optional(3).map(str::to_string),
+        score: optional(4).map(|s| parse_field(s, "score")).transpose()?,
+        strand,
+        thick_start,
+        thick_end,
+        item_rgb: optional(8).map(str::to_string),
+        blocks,
+    })
+}
+
+fn parse_field<N: FromStr>(s: &str, name: &str) -> Result<N, String> {
+    s.trim().parse().map_err(|_| format!("invalid {} '{}'", name, s))
+}
+
+fn parse_list(s: &str, name: &str) -> Result<Vec<u64>, String> {
+    s.trim_end_matches(',').split(',').map(|v| parse_field(v, name)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NClist;
+
+    #[test]
+    fn read() {
+        let data = "browser position chr1:1-100\n# comment\n\nchr1\t0\t10\nchr1 5 8 a 100 -\n\
+                    chr2\t10\t40\ttx\t.\t+\t12\t38\t255,0,0\t2\t10,5,\t0,25\n";
+        let records: Vec<_> = Reader::new(data.as_bytes()).collect::<Result<_, _>>().unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[0].name, None);
+        assert_eq!(records[0].strand, Strand::Unknown);
+        assert_eq!(records[1].name.as_deref(), Some("a"));
+        assert_eq!(records[1].score, Some(100.0));
+        assert_eq!(records[1].strand, Strand::Reverse);
+        assert_eq!(records[2].score, None);
+        assert_eq!(records[2].thick_start, Some(12));
+        assert_eq!(records[2].item_rgb.as_deref(), Some("255,0,0"));
+        assert_eq!(records[2].blocks, Some(vec![10..20, 35..40]));
+
+        let nclist = NClist::from_vec(records).unwrap();
+        assert_eq!(nclist.count_overlaps(&(7..12)), 3);
+        assert_eq!(nclist.count_overlaps(&(10..12)), 1);
+    }
+
+    #[test]
+    fn errors() {
+        let data = "chr1\t0\t10\nchr1\t5\n";
+        let err = Reader::new(data.as_bytes()).nth(1).unwrap().unwrap_err();
+        assert!(matches!(err, Error::Parse { line: 2, .. }));
+
+        for line in &["chr1\tx\t10", "chr1\t10\t5", "chr1\t0\t10\ta\t0\t*", "chr1\t0\t10\ta\t0\t+\t0\t10\t0\t2",
+                      "chr1\t0\t10\ta\t0\t+\t0\t12", "chr1\t5\t10\ta\t0\t+\t8\t6",
+                      "chr1\t0\t10\ta\t0\t+\t0\t10\t0\t1\t11\t0",
+                      "chr1\t10\t20\ta\t0\t+\t10\t20\t0\t1\t1\t18446744073709551610"] {
+            assert!(Reader::new(line.as_bytes()).next().unwrap().is_err(), "{}", line);
+        }
+    }
+
+    #[test]
+    fn header_lines() {
+        let data = "track
+track name=a
+browser	hide all
+track_contig_1	0	10
+browserish 5 8
+";
+        let records: Vec<_> = Reader::new(data.as_bytes()).collect::<Result<_, _>>().unwrap();
+        let chroms: Vec<_> = records.iter().map(|r| r.chrom.as_str()).collect();
+        assert_eq!(chroms, vec!["track_contig_1", "browserish"]);
+    }
+}
@@ -0,0 +1,185 @@
+//! Containment queries on an `NClist<T>`.
+//!
+//! The sublists of an `NClist<T>` hold exactly the intervals contained in their parent. When an
+//! interval is inside the query all its sublists are inside as well and are returned without
+//! further checks. When an interval does not contain the query, none of its sublists can, so
+//! `containing` only descends into matching intervals.
+//!
+//! The end of an element is compared with the end of the query using the `Semantics` of the
+//! element and the bound of the query, so closed elements are compared with inclusive queries like
+//! `RangeInclusive` and half-open elements with `Range` queries.
+use std::collections::VecDeque;
+use std::ops::Bound;
+
+use crate::query::{self, Query};
+use crate::{Interval, Layout, NClist};
+
+/// Iterator over the intervals inside a query, created by `NClist::contained_in`.
+pub struct ContainedIn<'a, T> where T: 'a + Interval {
+    nclist: &'a NClist<T>,
+    start: Option<&'a T::Coord>,
+    end: Bound<&'a T::Coord>,
+    current_pos: usize,
+    current_end: usize,
+    current_inside: bool,
+    sublists: VecDeque<(usize, usize, bool)>,
+}
+
+/// Iterator over the intervals enclosing a query, created by `NClist::containing`.
+pub struct Containing<'a, T> where T: 'a + Interval {
+    nclist: &'a NClist<T>,
+    start: Option<&'a T::Coord>,
+    end: Bound<&'a T::Coord>,
+    current_pos: usize,
+    current_end: usize,
+    sublists: VecDeque<(usize, usize)>,
+}
+
+impl<T> NClist<T> where T: Interval {
+    /// Returns an iterator over the elements that are inside query `q`, i.e. that start at or
+    /// after the start of `q` and end at or before the end of `q`.
+    pub fn contained_in<'a, Q>(&'a self, q: &'a Q) -> ContainedIn<'a, T> where Q: Query<T::Coord> + ?Sized {
+        let &(start, end) = self.contained[0].as_ref().unwrap();
+        let mut it = ContainedIn { nclist: self, start: q.lower(), end: q.upper(), current_pos: end, current_end: end, current_inside: false, sublists: VecDeque::new() };
+        if !query::is_empty(it.start, it.end) {
+            it.sublists.push_back((start, end, false));
+        }
+        it
+    }
+
+    /// Returns an iterator over the elements that enclose query `q`, i.e. that start at or
+    /// before the start of `q` and end at or after the end of `q`.
+    pub fn containing<'a, Q>(&'a self, q: &'a Q) -> Containing<'a, T> where Q: Query<T::Coord> + ?Sized {
+        let &(start, end) = self.contained[0].as_ref().unwrap();
+        let mut it = Containing { nclist: self, start: q.lower(), end: q.upper(), current_pos: end, current_end: end, sublists: VecDeque::new() };
+        //no element encloses a query without a start or end
+        if !query::is_empty(it.start, it.end) && it.start.is_some() && it.end != Bound::Unbounded {
+            it.sublists.push_back((start, end));
+        }
+        it
+    }
+}
+
+impl<'a, T> Iterator for ContainedIn<'a, T> where T: Interval {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        let nclist = self.nclist;
+        loop {
+            while self.current_pos < self.current_end {
+                let pos = self.current_pos;
+                let e = &nclist.intervals[pos];
+                if !self.current_inside && !query::starts_before(e.start(), self.end) {
+                    break;
+                }
+                self.current_pos += 1;
+
+                let inside = self.current_inside || (self.start <= Some(e.start()) && query::ends_within(e.upper(), self.end));
+                if let Some((start, end)) = nclist.contained[pos + 1] {
+                    self.sublists.push_back((start, end, inside));
+                }
+                if inside {
+                    return Some(e);
+                }
+            }
+
+            let (start, end, inside) = self.sublists.pop_front()?;
+            self.current_pos = if inside {
+                start
+            } else {
+                start + nclist.skip_ending_before(start, end, self.start)
+            };
+            self.current_end = end;
+            self.current_inside = inside;
+        }
+    }
+}
+
+impl<'a, T> Iterator for Containing<'a, T> where T: Interval {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        let nclist = self.nclist;
+        loop {
+            if self.current_pos < self.current_end {
+                let pos = self.current_pos;
+                let e = &nclist.intervals[pos];
+                if Some(e.start()) <= self.start {
+                    self.current_pos += 1;
+                    if let Some(sublist) = nclist.contained[pos + 1] {
+                        self.sublists.push_back(sublist);
+                    }
+                    return Some(e);
+                }
+            }
+
+            let (start, end) = self.sublists.pop_front()?;
+            let q_end = self.end;
+            self.current_pos = start + nclist.intervals[start..end].partition_point(|e| !query::ends_within(q_end, e.upper()));
+            self.current_end = end;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{random_intervals, Site};
+    use crate::NClist;
+    use std::ops::Range;
+
+    fn sorted<'a>(it: impl Iterator<Item = &'a Range<u32>>) -> Vec<Range<u32>> {
+        let mut v: Vec<_> = it.cloned().collect();
+        v.sort_by(|a, b| a.start.cmp(&b.start).then(b.end.cmp(&a.end)));
+        v
+    }
+
+    #[test]
+    fn contained_in() {
+        let nclist = NClist::from_vec(vec![(0..100), (10..20), (12..15), (15..30), (18..19), (40..50)]).unwrap();
+        assert_eq!(sorted(nclist.contained_in(&(10..20))), vec![10..20, 12..15, 18..19]);
+        assert_eq!(sorted(nclist.contained_in(&(5..35))), vec![10..20, 12..15, 15..30, 18..19]);
+        assert_eq!(sorted(nclist.contained_in(&(13..16))), vec![]);
+        assert_eq!(nclist.contained_in(&(0..100)).count(), 6);
+        assert_eq!(nclist.contained_in(&(20..20)).count(), 0);
+    }
+
+    #[test]
+    fn containing() {
+        let nclist = NClist::from_vec(vec![(0..100), (10..20), (12..15), (15..30), (18..19), (40..50)]).unwrap();
+        assert_eq!(sorted(nclist.containing(&(12..15))), vec![0..100, 10..20, 12..15]);
+        assert_eq!(sorted(nclist.containing(&(16..20))), vec![0..100, 10..20, 15..30]);
+        assert_eq!(sorted(nclist.containing(&(35..60))), vec![0..100]);
+        assert_eq!(nclist.containing(&(90..110)).count(), 0);
+        assert_eq!(nclist.containing(&(15..15)).count(), 0);
+    }
+
+    #[test]
+    fn closed_and_empty() {
+        let nclist = NClist::from_vec(vec![(1u32..=5), (5..=5), (5..=8), (9..=9)]).unwrap();
+        let found: Vec<_> = nclist.contained_in(&(5..=8)).cloned().collect();
+        assert_eq!(found, vec![5..=8, 5..=5]);
+        assert_eq!(nclist.contained_in(&(9..=9)).count(), 1);
+        assert_eq!(nclist.contained_in(&(..)).count(), 4);
+        let found: Vec<_> = nclist.containing(&(5..=5)).cloned().collect();
+        assert_eq!(found, vec![1..=5, 5..=8, 5..=5]);
+        assert_eq!(nclist.containing(&(8..=9)).count(), 0);
+
+        let sites = NClist::from_vec(vec![Site(5..5), Site(3..8), Site(8..8)]).unwrap();
+        let found: Vec<_> = sites.contained_in(&Site(4..8)).map(|s| s.0.clone()).collect();
+        assert_eq!(found, vec![5..5]);
+        assert_eq!(sites.contained_in(&Site(8..8)).count(), 1);
+        let found: Vec<_> = sites.containing(&Site(5..5)).map(|s| s.0.clone()).collect();
+        assert_eq!(found, vec![3..8, 5..5]);
+        assert_eq!(sites.containing(&Site(8..8)).count(), 1);
+    }
+
+    #[test]
+    fn matches_filtered_overlaps() {
+        let v = random_intervals(300, 200, 40);
+        let nclist = NClist::from_vec(v).unwrap();
+        for q in (0..220).step_by(3).map(|s| s..s + 1 + s % 25) {
+            let inside = nclist.overlaps(&q).filter(|e| e.start >= q.start && e.end <= q.end);
+            assert_eq!(sorted(nclist.contained_in(&q)), sorted(inside));
+            let enclosing = nclist.overlaps(&q).filter(|e| e.start <= q.start && e.end >= q.end);
+            assert_eq!(sorted(nclist.containing(&q)), sorted(enclosing));
+        }
+    }
+}
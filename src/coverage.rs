@@ -0,0 +1,95 @@
+//! Coverage depth of the elements in an `NClist` over a region.
+use std::ops::{Range, Sub};
+
+use num_traits::ToPrimitive;
+
+use crate::{Interval, NClist};
+
+impl<T> NClist<T> where T: Interval, T::Coord: Clone {
+    /// Returns the coverage depth over `r` as a run-length encoded track. Every segment holds the
+    /// number of elements overlapping all its positions. The segments are ordered, adjacent
+    /// segments have a different depth and together they cover `r` completely, segments with
+    /// depth zero included.
+    pub fn coverage(&self, r: &Range<T::Coord>) -> Vec<(Range<T::Coord>, usize)> {
+        let mut segments = Vec::new();
+        if r.end <= r.start {
+            return segments;
+        }
+
+        //depth changes, clipped to the query
+        let mut starts: Vec<&T::Coord> = Vec::new();
+        let mut ends: Vec<&T::Coord> = Vec::new();
+        for e in self.overlaps(r) {
+            starts.push(e.start().max(&r.start));
+            ends.push(e.end().min(&r.end));
+        }
+        starts.sort();
+        ends.sort();
+
+        let (mut s, mut e) = (0, 0);
+        let mut depth = 0;
+        let mut pos = &r.start;
+        while pos < &r.end {
+            while s < starts.len() && starts[s] == pos {
+                depth += 1;
+                s += 1;
+            }
+            while e < ends.len() && ends[e] == pos {
+                depth -= 1;
+                e += 1;
+            }
+            let next = [starts.get(s), ends.get(e)].iter()
+                .filter_map(|p| p.copied())
+                .min()
+                .unwrap_or(&r.end);
+            match segments.last_mut() {
+                //an element ending where another starts leaves the depth unchanged
+                Some((last, d)) if *d == depth => last.end = next.clone(),
+                _ => segments.push((pos.clone()..next.clone(), depth)),
+            }
+            pos = next;
+        }
+        segments
+    }
+
+    /// Returns the maximum number of elements overlapping a single position in `r`.
+    pub fn max_depth(&self, r: &Range<T::Coord>) -> usize {
+        self.coverage(r).into_iter().map(|(_, depth)| depth).max().unwrap_or(0)
+    }
+
+    /// Returns the mean coverage depth over all positions in `r`. Returns zero for an empty range.
+    pub fn mean_depth(&self, r: &Range<T::Coord>) -> f64 where T::Coord: Sub<Output = T::Coord> + ToPrimitive {
+        let length = |r: &Range<T::Coord>| (r.end.clone() - r.start.clone()).to_f64().unwrap_or(0.0);
+        let total = self.coverage(r).iter()
+            .map(|(segment, depth)| length(segment) * *depth as f64)
+            .sum::<f64>();
+        if r.end > r.start {
+            total / length(r)
+        } else {
+            0.0
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::NClist;
+
+    #[test]
+    fn coverage() {
+        let nclist = NClist::from_vec(vec![(5u32..10), (6..8), (8..12), (20..25)]).unwrap();
+        assert_eq!(nclist.coverage(&(0..30)), vec![
+            (0..5, 0), (5..6, 1), (6..10, 2), (10..12, 1), (12..20, 0), (20..25, 1), (25..30, 0),
+        ]);
+        assert_eq!(nclist.coverage(&(7..9)), vec![(7..9, 2)]);
+        assert_eq!(nclist.coverage(&(13..14)), vec![(13..14, 0)]);
+        assert_eq!(nclist.coverage(&(13..13)), vec![]);
+
+        assert_eq!(nclist.max_depth(&(0..30)), 2);
+        assert_eq!(nclist.max_depth(&(11..22)), 1);
+        assert_eq!(nclist.max_depth(&(13..13)), 0);
+        assert_eq!(nclist.mean_depth(&(0..30)), 16.0 / 30.0);
+        assert_eq!(nclist.mean_depth(&(6..8)), 2.0);
+        assert_eq!(nclist.mean_depth(&(6..6)), 0.0);
+    }
+}
@@ -0,0 +1,389 @@
+//! On-disk storage of an `NClist<T>`.
+//!
+//! The flattened `intervals` and `contained` vectors of an `NClist<T>` can be written to a file
+//! with `NClist::write_to` when `T` has a fixed size binary encoding (`FixedSize`). A
+//! `DiskNClist` reads this format directly from a byte buffer, for example a memory-mapped file,
+//! and decodes only the intervals that are visited during a query. The file is never loaded
+//! completely, and a memory-mapped file can be shared by multiple processes. Memory-mapping a file
+//! with `DiskNClist::open` requires the `disk` feature.
+//!
+//! The layout of the file is (all numbers little-endian):
+//!
+//! | bytes             | content                                                  |
+//! |-------------------|----------------------------------------------------------|
+//! | 8                 | magic `NCLIST\0\x01`                                     |
+//! | 8                 | encoded size of a single interval as `u64`              |
+//! | 8                 | number of intervals `n` as `u64`                        |
+//! | 16 * (n + 1)      | sublist `(start, end)` pairs, `u64::MAX` for no sublist |
+//! | size * n          | encoded intervals                                        |
+#[cfg(all(unix, feature = "disk"))]
+use std::fs::File;
+use std::io::{self, Write};
+use std::marker::PhantomData;
+use std::ops::{Bound, Range, RangeInclusive};
+#[cfg(all(unix, feature = "disk"))]
+use std::path::Path;
+
+use crate::query::{self, Query};
+use crate::{Interval, Layout, NClist, OverlapsState};
+
+const MAGIC: &[u8; 8] = b"NCLIST\0\x01";
+const HEADER_SIZE: usize = 24;
+const NO_SUBLIST: u64 = u64::MAX;
+
+/// Interval types with a fixed size binary encoding that can be stored in a `DiskNClist`.
+pub trait FixedSize: Interval + Sized {
+    /// The number of bytes used by the encoding.
+    const SIZE: usize;
+
+    /// Encode the interval in `buf`, which is exactly `SIZE` bytes long.
+    fn write_bytes(&self, buf: &mut [u8]);
+
+    /// Decode an interval from `buf`, which is exactly `SIZE` bytes long.
+    fn read_bytes(buf: &[u8]) -> Self;
+}
+
+macro_rules! impl_fixed_size_range {
+    ($($t:ty),*) => {$(
+        /// `Range` is stored as the little-endian start and end coordinate.
+        impl FixedSize for Range<$t> {
+            const SIZE: usize = 2 * std::mem::size_of::<$t>();
+
+            fn write_bytes(&self, buf: &mut [u8]) {
+                let (start, end) = buf.split_at_mut(Self::SIZE / 2);
+                start.copy_from_slice(&self.start.to_le_bytes());
+                end.copy_from_slice(&self.end.to_le_bytes());
+            }
+
+            fn read_bytes(buf: &[u8]) -> Self {
+                let (start, end) = buf.split_at(Self::SIZE / 2);
+                let mut b = [0; std::mem::size_of::<$t>()];
+                b.copy_from_slice(start);
+                let start = <$t>::from_le_bytes(b);
+                b.copy_from_slice(end);
+                start..<$t>::from_le_bytes(b)
+            }
+        }
+
+        /// `RangeInclusive` is stored as the little-endian start and end coordinate.
+        impl FixedSize for RangeInclusive<$t> {
+            const SIZE: usize = 2 * std::mem::size_of::<$t>();
+
+            fn write_bytes(&self, buf: &mut [u8]) {
+                let (start, end) = buf.split_at_mut(Self::SIZE / 2);
+                start.copy_from_slice(&self.start().to_le_bytes());
+                end.copy_from_slice(&self.end().to_le_bytes());
+            }
+
+            fn read_bytes(buf: &[u8]) -> Self {
+                let (start, end) = buf.split_at(Self::SIZE / 2);
+                let mut b = [0; std::mem::size_of::<$t>()];
+                b.copy_from_slice(start);
+                let start = <$t>::from_le_bytes(b);
+                b.copy_from_slice(end);
+                start..=<$t>::from_le_bytes(b)
+            }
+        }
+    )*}
+}
+
+impl_fixed_size_range!(u8, u16, u32, u64, i8, i16, i32, i64);
+
+impl<T> NClist<T> where T: FixedSize {
+    /// Write the `NClist` in the on-disk format that can be read by a `DiskNClist`.
+    pub fn write_to<W: Write>(&self, mut w: W) -> io::Result<()> {
+        w.write_all(MAGIC)?;
+        w.write_all(&(T::SIZE as u64).to_le_bytes())?;
+        w.write_all(&(self.intervals.len() as u64).to_le_bytes())?;
+        for c in &self.contained {
+            let (start, end) = c.map_or((NO_SUBLIST, NO_SUBLIST), |(s, e)| (s as u64, e as u64));
+            w.write_all(&start.to_le_bytes())?;
+            w.write_all(&end.to_le_bytes())?;
+        }
+        let mut buf = vec![0; T::SIZE];
+        for e in &self.intervals {
+            e.write_bytes(&mut buf);
+            w.write_all(&buf)?;
+        }
+        w.flush()
+    }
+}
+
+/// A read-only memory map of a file.
+#[cfg(all(unix, feature = "disk"))]
+pub struct Mmap {
+    ptr: *mut libc::c_void,
+    len: usize,
+}
+
+#[cfg(all(unix, feature = "disk"))]
+impl Mmap {
+    /// Map the complete file `f` in memory.
+    ///
+    /// # Safety
+    /// The mapping is shared with the file. The caller must ensure that the file is not modified
+    /// or truncated, by this or any other process, while the `Mmap` is alive. Otherwise the
+    /// contents of the slice can change or accessing it can abort the process with `SIGBUS`.
+    pub unsafe fn map(f: &File) -> io::Result<Mmap> {
+        use std::os::unix::io::AsRawFd;
+
+        let len = f.metadata()?.len() as usize;
+        if len == 0 {
+            return Ok(Mmap { ptr: std::ptr::null_mut(), len });
+        }
+        // SAFETY: a new read-only shared mapping is created, the result is checked below.
+        let ptr = libc::mmap(std::ptr::null_mut(), len, libc::PROT_READ, libc::MAP_SHARED, f.as_raw_fd(), 0);
+        if ptr == libc::MAP_FAILED {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(Mmap { ptr, len })
+        }
+    }
+}
+
+#[cfg(all(unix, feature = "disk"))]
+impl AsRef<[u8]> for Mmap {
+    fn as_ref(&self) -> &[u8] {
+        if self.len == 0 {
+            &[]
+        } else {
+            // SAFETY: the mapping is valid and readable for `len` bytes until dropped.
+            unsafe { std::slice::from_raw_parts(self.ptr as *const u8, self.len) }
+        }
+    }
+}
+
+#[cfg(all(unix, feature = "disk"))]
+impl Drop for Mmap {
+    fn drop(&mut self) {
+        if self.len > 0 {
+            // SAFETY: `ptr` and `len` describe a mapping created in `Mmap::map`.
+            unsafe { libc::munmap(self.ptr, self.len); }
+        }
+    }
+}
+
+// SAFETY: the mapping is read-only and not tied to the creating thread.
+#[cfg(all(unix, feature = "disk"))]
+unsafe impl Send for Mmap {}
+#[cfg(all(unix, feature = "disk"))]
+unsafe impl Sync for Mmap {}
+
+/// An `NClist<T>` stored in the on-disk format. Intervals are decoded when they are visited by a
+/// query. The storage `B` can be any byte buffer, `DiskNClist::open` uses a memory-mapped file.
+///
+/// The file contents are checked for a valid header and size when a `DiskNClist` is created. The
+/// nesting structure is trusted, a corrupted file can cause a panic during queries.
+pub struct DiskNClist<T, B> {
+    data: B,
+    len: usize,
+    _marker: PhantomData<T>,
+}
+
+/// Iterator over the elements of a `DiskNClist` overlapping a query, created by
+/// `DiskNClist::overlaps`.
+pub struct DiskOverlaps<'a, T, B> where T: FixedSize {
+    nclist: &'a DiskNClist<T, B>,
+    start: Option<&'a T::Coord>,
+    end: Bound<&'a T::Coord>,
+    state: OverlapsState,
+}
+
+#[cfg(all(unix, feature = "disk"))]
+impl<T> DiskNClist<T, Mmap> where T: FixedSize {
+    /// Memory-map the file at `path` and use it as an `NClist<T>`.
+    ///
+    /// # Safety
+    /// The file is mapped with `Mmap::map` and the same contract applies: the file must not be
+    /// modified or truncated while the `DiskNClist` is alive.
+    pub unsafe fn open<P: AsRef<Path>>(path: P) -> io::Result<DiskNClist<T, Mmap>> {
+        let f = File::open(path)?;
+        DiskNClist::from_bytes(Mmap::map(&f)?)
+    }
+}
+
+impl<T, B> DiskNClist<T, B> where T: FixedSize, B: AsRef<[u8]> {
+    /// Use the bytes in `data` as an `NClist<T>`. The data should have been created with
+    /// `NClist::write_to`.
+    pub fn from_bytes(data: B) -> io::Result<DiskNClist<T, B>> {
+        let invalid = |msg| io::Error::new(io::ErrorKind::InvalidData, msg);
+        let bytes = data.as_ref();
+        if bytes.len() < HEADER_SIZE || &bytes[..8] != MAGIC {
+            return Err(invalid("Not an NClist file"));
+        }
+        if read_u64(bytes, 8) != T::SIZE as u64 {
+            return Err(invalid("NClist file contains a different interval type"));
+        }
+        let len = read_u64(bytes, 16) as usize;
+        let expected = len.checked_add(1).and_then(|n| n.checked_mul(16))
+            .and_then(|c| len.checked_mul(T::SIZE).and_then(|i| i.checked_add(c)))
+            .and_then(|s| s.checked_add(HEADER_SIZE));
+        if expected != Some(bytes.len()) {
+            return Err(invalid("NClist file has an unexpected size"));
+        }
+        Ok(DiskNClist { data, len, _marker: PhantomData })
+    }
+
+    /// Returns the number of intervals.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the list contains no intervals.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Count the number of elements overlapping query `q`.
+    pub fn count_overlaps<Q>(&self, q: &Q) -> usize where Q: Query<T::Coord> + ?Sized {
+        self.overlaps(q).count()
+    }
+
+    /// Returns an iterator that returns overlapping elements to query `q`. The intervals are
+    /// decoded and returned by value.
+    pub fn overlaps<'a, Q>(&'a self, q: &'a Q) -> DiskOverlaps<'a, T, B> where Q: Query<T::Coord> + ?Sized {
+        let (start, end) = (q.lower(), q.upper());
+        DiskOverlaps { nclist: self, start, end, state: OverlapsState::new(self, start, end) }
+    }
+
+    fn interval(&self, i: usize) -> T {
+        let offset = HEADER_SIZE + 16 * (self.len + 1) + i * T::SIZE;
+        T::read_bytes(&self.data.as_ref()[offset..offset + T::SIZE])
+    }
+}
+
+impl<T, B> Layout for DiskNClist<T, B> where T: FixedSize, B: AsRef<[u8]> {
+    type Item = T;
+
+    #[inline]
+    fn starts_before(&self, i: usize, q_end: Bound<&T::Coord>) -> bool {
+        query::starts_before(self.interval(i).start(), q_end)
+    }
+
+    fn sublist(&self, i: usize) -> Option<(usize, usize)> {
+        let offset = HEADER_SIZE + 16 * i;
+        let bytes = self.data.as_ref();
+        let start = read_u64(bytes, offset);
+        if start == NO_SUBLIST {
+            None
+        } else {
+            Some((start as usize, read_u64(bytes, offset + 8) as usize))
+        }
+    }
+
+    /// Binary search on the decoded intervals, see `count_ending_before`.
+    fn skip_ending_before(&self, start: usize, end: usize, q: Option<&T::Coord>) -> usize {
+        let q = match q {
+            Some(q) => q,
+            None => return 0,
+        };
+        let (mut lo, mut hi) = (start, end);
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if query::ends_before(&self.interval(mid), q) {
+                lo = mid + 1;
+            } else {
+                hi = mid;
+            }
+        }
+        lo - start
+    }
+}
+
+impl<'a, T, B> Iterator for DiskOverlaps<'a, T, B> where T: FixedSize, B: AsRef<[u8]> {
+    type Item = T;
+    fn next(&mut self) -> Option<Self::Item> {
+        let nclist = self.nclist;
+        self.state.next_index(nclist, self.start, self.end).map(|i| nclist.interval(i))
+    }
+}
+
+fn read_u64(bytes: &[u8], offset: usize) -> u64 {
+    let mut b = [0; 8];
+    b.copy_from_slice(&bytes[offset..offset + 8]);
+    u64::from_le_bytes(b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::{random_intervals, Site};
+
+    impl FixedSize for Site {
+        const SIZE: usize = 8;
+
+        fn write_bytes(&self, buf: &mut [u8]) {
+            self.0.write_bytes(buf)
+        }
+
+        fn read_bytes(buf: &[u8]) -> Self {
+            Site(Range::read_bytes(buf))
+        }
+    }
+
+    #[test]
+    fn roundtrip() {
+        let list = random_intervals(100, 50, 13);
+        let nclist = NClist::from_vec(list).unwrap();
+        let mut buf = Vec::new();
+        nclist.write_to(&mut buf).unwrap();
+
+        let disk: DiskNClist<Range<u32>, _> = DiskNClist::from_bytes(buf).unwrap();
+        assert_eq!(disk.len(), 100);
+        for q in (0..70).map(|s| s..s + 3) {
+            assert_eq!(disk.count_overlaps(&q), nclist.count_overlaps(&q));
+            assert!(disk.overlaps(&q).eq(nclist.overlaps(&q).cloned()));
+        }
+        assert_eq!(disk.count_overlaps(&(10..10)), 0);
+        assert_eq!(disk.overlaps(&(10..10)).count(), 0);
+    }
+
+    #[test]
+    fn closed_and_empty() {
+        let nclist = NClist::from_vec(vec![(1u32..=5), (5..=8), (9..=9), (6..=6)]).unwrap();
+        let mut buf = Vec::new();
+        nclist.write_to(&mut buf).unwrap();
+        let disk: DiskNClist<RangeInclusive<u32>, _> = DiskNClist::from_bytes(buf).unwrap();
+        for q in &[0..1, 0..2, 5..6, 6..7, 8..9, 9..10, 10..20] {
+            assert_eq!(disk.count_overlaps(q), nclist.count_overlaps(q));
+            assert!(disk.overlaps(q).eq(nclist.overlaps(q).cloned()));
+        }
+        assert_eq!(disk.count_overlaps(&(5..=5)), 2);
+        assert_eq!(disk.count_overlaps(&(..)), 4);
+
+        let nclist = NClist::from_vec(vec![Site(5..5), Site(3..8), Site(8..8)]).unwrap();
+        let mut buf = Vec::new();
+        nclist.write_to(&mut buf).unwrap();
+        let disk: DiskNClist<Site, _> = DiskNClist::from_bytes(buf).unwrap();
+        for q in &[Site(0..5), Site(4..6), Site(5..5), Site(8..8), Site(8..9)] {
+            assert_eq!(disk.count_overlaps(q), nclist.count_overlaps(q));
+        }
+        assert_eq!(disk.count_overlaps(&Site(5..5)), 2);
+    }
+
+    #[test]
+    fn invalid_data() {
+        let nclist = NClist::from_vec(vec![(10u32..15), (10..20), (1..8)]).unwrap();
+        let mut buf = Vec::new();
+        nclist.write_to(&mut buf).unwrap();
+
+        assert!(DiskNClist::<Range<u64>, _>::from_bytes(&buf).is_err());
+        assert!(DiskNClist::<Range<u32>, _>::from_bytes(&buf[..buf.len() - 1]).is_err());
+        assert!(DiskNClist::<Range<u32>, _>::from_bytes(&buf[1..]).is_err());
+        assert!(DiskNClist::<Range<u32>, _>::from_bytes(&buf).is_ok());
+    }
+
+    #[cfg(all(unix, feature = "disk"))]
+    #[test]
+    fn open_file() {
+        let nclist = NClist::from_vec(vec![(10u64..15), (10..20), (1..8)]).unwrap();
+        let path = std::env::temp_dir().join(format!("nclist-test-{}.ncl", std::process::id()));
+        nclist.write_to(io::BufWriter::new(File::create(&path).unwrap())).unwrap();
+
+        // SAFETY: the file is not modified until the mapping is dropped.
+        let disk: DiskNClist<Range<u64>, Mmap> = unsafe { DiskNClist::open(&path).unwrap() };
+        assert_eq!(disk.count_overlaps(&(5..20)), 3);
+        assert_eq!(disk.overlaps(&(7..10)).collect::<Vec<_>>(), vec![1..8]);
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,324 @@
+//! A mutable wrapper around `NClist<T>` that supports insertion and removal of intervals.
+//!
+//! The `NClist<T>` layout is a single flattened `Vec` and cannot be updated in place. The
+//! `DynamicNClist<T>` keeps a small number of `NClist` levels with geometrically decreasing sizes
+//! (the logarithmic method). An insert creates a new level of size one and merges levels of
+//! similar size, so every interval takes part in `O(log(N))` rebuilds. Removed intervals are
+//! marked and skipped during queries. A level is rebuilt when more than half of its intervals has
+//! been removed.
+use std::convert::TryFrom;
+use std::ops::Bound;
+
+use crate::{Error, Interval, NClist, OrderedOverlaps, Overlaps, Query};
+
+#[derive(Debug)]
+struct Level<T> where T: Interval {
+    nclist: NClist<T>,
+    removed: Vec<bool>,
+    n_removed: usize,
+}
+
+/// An `NClist<T>` that allows insertion and removal of intervals without rebuilding the complete
+/// list. Queries return the same results as an `NClist<T>` created from the current set of
+/// intervals.
+#[derive(Debug)]
+pub struct DynamicNClist<T> where T: Interval {
+    levels: Vec<Level<T>>,
+}
+
+pub struct DynamicOverlaps<'a, T> where T: 'a + Interval {
+    levels: std::slice::Iter<'a, Level<T>>,
+    current: Option<(&'a Level<T>, Overlaps<'a, T>)>,
+    start: Option<&'a T::Coord>,
+    end: Bound<&'a T::Coord>,
+}
+
+pub struct DynamicOrderedOverlaps<'a, T> where T: 'a + Interval {
+    heads: Vec<(Option<&'a T>, &'a Level<T>, OrderedOverlaps<'a, T>)>,
+}
+
+impl<T> Level<T> where T: Interval {
+    fn new(nclist: NClist<T>) -> Level<T> {
+        let removed = vec![false; nclist.len()];
+        Level { nclist, removed, n_removed: 0 }
+    }
+
+    fn len(&self) -> usize {
+        self.nclist.len() - self.n_removed
+    }
+
+    fn into_live(self) -> impl Iterator<Item = T> {
+        self.nclist.intervals.into_iter()
+            .zip(self.removed)
+            .filter_map(|(e, removed)| if removed { None } else { Some(e) })
+    }
+}
+
+impl<T> DynamicNClist<T> where T: Interval {
+    /// Create an empty `DynamicNClist`.
+    pub fn new() -> DynamicNClist<T> {
+        DynamicNClist { levels: Vec::new() }
+    }
+
+    /// Create a `DynamicNClist` from a `Vec<T>`. The same validation as `NClist::from_vec` is
+    /// applied.
+    pub fn from_vec(v: Vec<T>) -> Result<DynamicNClist<T>, Error<T>> {
+        NClist::from_vec(v).map(DynamicNClist::from)
+    }
+
+    /// Returns the number of intervals.
+    pub fn len(&self) -> usize {
+        self.levels.iter().map(Level::len).sum()
+    }
+
+    /// Returns `true` if there are no intervals.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Insert interval `e`. This fails when `e` is not valid for its `Semantics`.
+    pub fn insert(&mut self, e: T) -> Result<(), Error<T>> {
+        let nclist = NClist::from_vec(vec![e])?;
+        self.levels.push(Level::new(nclist));
+
+        //merge the smallest levels while they are of similar size
+        while self.levels.len() > 1 {
+            let n = self.levels.len();
+            if self.levels[n - 2].len() > self.levels[n - 1].len() {
+                break;
+            }
+            let last = self.levels.pop().unwrap();
+            let prev = self.levels.pop().unwrap();
+            self.push_merged(vec![prev, last]);
+        }
+        Ok(())
+    }
+
+    /// Remove a single interval equal to `e`. Returns `true` if an interval was removed.
+    pub fn remove(&mut self, e: &T) -> bool where T: PartialEq {
+        let found = self.levels.iter().enumerate().find_map(|(l, level)| {
+            let mut it = level.nclist.overlaps(e);
+            while let Some(i) = it.next_index() {
+                if !level.removed[i] && level.nclist.intervals[i] == *e {
+                    return Some((l, i));
+                }
+            }
+            None
+        });
+
+        if let Some((l, i)) = found {
+            let level = &mut self.levels[l];
+            level.removed[i] = true;
+            level.n_removed += 1;
+            if level.n_removed * 2 > level.nclist.len() {
+                let level = self.levels.remove(l);
+                self.push_merged(vec![level]);
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Merge all levels into a single `NClist`. Queries on a compacted list are as fast as on a
+    /// regular `NClist<T>`.
+    pub fn compact(&mut self) {
+        if self.levels.len() > 1 || self.levels.iter().any(|l| l.n_removed > 0) {
+            let levels = std::mem::take(&mut self.levels);
+            self.push_merged(levels);
+        }
+    }
+
+    /// Count the number of elements overlapping query `r`.
+    pub fn count_overlaps<Q>(&self, r: &Q) -> usize where Q: Query<T::Coord> + ?Sized {
+        self.levels.iter().map(|level| {
+            if level.n_removed == 0 {
+                level.nclist.count_overlaps(r)
+            } else {
+                let mut count = 0;
+                let mut it = level.nclist.overlaps(r);
+                while let Some(i) = it.next_index() {
+                    if !level.removed[i] {
+                        count += 1;
+                    }
+                }
+                count
+            }
+        }).sum()
+    }
+
+    /// Returns an iterator that returns overlapping elements to query `r`.
+    pub fn overlaps<'a, Q>(&'a self, r: &'a Q) -> DynamicOverlaps<'a, T> where Q: Query<T::Coord> + ?Sized {
+        DynamicOverlaps { levels: self.levels.iter(), current: None, start: r.lower(), end: r.upper() }
+    }
+
+    /// Returns an iterator that returns overlapping elements to query `r` ordered by start
+    /// coordinate.
+    pub fn overlaps_ordered<'a, Q>(&'a self, r: &'a Q) -> DynamicOrderedOverlaps<'a, T> where Q: Query<T::Coord> + ?Sized {
+        let heads = self.levels.iter()
+            .map(|level| {
+                let mut it = level.nclist.overlaps_ordered(r);
+                (next_live(level, &mut it), level, it)
+            })
+            .collect();
+        DynamicOrderedOverlaps { heads }
+    }
+
+    /// Compact the levels and return the resulting `NClist<T>`.
+    pub fn into_nclist(mut self) -> NClist<T> {
+        self.compact();
+        self.levels.pop().map(|l| l.nclist).unwrap_or_else(|| NClist::build(Vec::new()))
+    }
+
+    /// Return the intervals `Vec`. The intervals are returned in an unspecified order.
+    pub fn into_vec(self) -> Vec<T> {
+        self.levels.into_iter().flat_map(Level::into_live).collect()
+    }
+
+    fn push_merged(&mut self, levels: Vec<Level<T>>) {
+        let v: Vec<T> = levels.into_iter().flat_map(Level::into_live).collect();
+        if !v.is_empty() {
+            self.levels.push(Level::new(NClist::build(v)));
+        }
+    }
+}
+
+impl<T> Default for DynamicNClist<T> where T: Interval {
+    fn default() -> Self {
+        DynamicNClist::new()
+    }
+}
+
+impl<T> From<NClist<T>> for DynamicNClist<T> where T: Interval {
+    fn from(nclist: NClist<T>) -> Self {
+        let mut list = DynamicNClist::new();
+        if !nclist.is_empty() {
+            list.levels.push(Level::new(nclist));
+        }
+        list
+    }
+}
+
+impl<T> TryFrom<Vec<T>> for DynamicNClist<T> where T: Interval {
+    type Error = Error<T>;
+    fn try_from(v: Vec<T>) -> Result<Self, Self::Error> {
+        DynamicNClist::from_vec(v)
+    }
+}
+
+#[inline]
+fn next_live<'a, T: Interval>(level: &'a Level<T>, it: &mut OrderedOverlaps<'a, T>) -> Option<&'a T> {
+    while let Some(i) = it.next_index() {
+        if !level.removed[i] {
+            return Some(&level.nclist.intervals[i]);
+        }
+    }
+    None
+}
+
+impl<'a, T> Iterator for DynamicOverlaps<'a, T> where T: Interval {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((level, ref mut it)) = self.current {
+                while let Some(i) = it.next_index() {
+                    if !level.removed[i] {
+                        return Some(&level.nclist.intervals[i]);
+                    }
+                }
+            }
+            let level = self.levels.next()?;
+            self.current = Some((level, level.nclist.overlaps_between(self.start, self.end)));
+        }
+    }
+}
+
+impl<'a, T> Iterator for DynamicOrderedOverlaps<'a, T> where T: Interval {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        //pick the level with the first interval in (start, reverse end) order
+        let mut first: Option<usize> = None;
+        for (l, (head, _, _)) in self.heads.iter().enumerate() {
+            if let Some(e) = head {
+                let before = match first.and_then(|f| self.heads[f].0) {
+                    Some(f) => e.start().cmp(f.start()).then(f.end().cmp(e.end())).is_lt(),
+                    None => true,
+                };
+                if before {
+                    first = Some(l);
+                }
+            }
+        }
+
+        let (head, level, it) = &mut self.heads[first?];
+        let e = head.take();
+        *head = next_live(level, it);
+        e
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tests::random_intervals;
+
+    #[test]
+    fn insert() {
+        let mut list = DynamicNClist::new();
+        for r in [(10..15), (10..20), (1..8), (12..13), (30..40)] {
+            list.insert(r).unwrap();
+        }
+        assert_eq!(list.len(), 5);
+        assert!(list.insert(7..7).is_err());
+
+        assert_eq!(list.count_overlaps(&(5..20)), 4);
+        assert_eq!(list.overlaps(&(11..13)).count(), 3);
+        let v: Vec<_> = list.overlaps_ordered(&(5..35)).collect();
+        assert_eq!(v, vec![&(1..8), &(10..20), &(10..15), &(12..13), &(30..40)]);
+    }
+
+    #[test]
+    fn remove() {
+        let mut list = DynamicNClist::from_vec(vec![(10..15), (10..20), (1..8), (10..15)]).unwrap();
+        list.insert(12..13).unwrap();
+
+        assert!(list.remove(&(10..15)));
+        assert_eq!(list.count_overlaps(&(10..12)), 2);
+        assert!(list.remove(&(10..20)));
+        assert!(!list.remove(&(10..20)));
+        assert!(!list.remove(&(50..60)));
+        assert!(list.remove(&(12..13)));
+
+        assert_eq!(list.len(), 2);
+        assert_eq!(list.count_overlaps(&(5..20)), 2);
+        let v: Vec<_> = list.overlaps_ordered(&(5..20)).collect();
+        assert_eq!(v, vec![&(1..8), &(10..15)]);
+        let mut v: Vec<_> = list.into_vec();
+        v.sort_by_key(|r| r.start);
+        assert_eq!(v, vec![(1..8), (10..15)]);
+    }
+
+    #[test]
+    fn matches_rebuilt_nclist() {
+        let mut list = DynamicNClist::new();
+        let mut v = Vec::new();
+        for (i, r) in random_intervals(200, 101, 17).into_iter().enumerate() {
+            list.insert(r.clone()).unwrap();
+            v.push(r);
+            if i % 3 == 0 {
+                let r = v.swap_remove((i * 5) % v.len());
+                assert!(list.remove(&r));
+            }
+        }
+        let nclist = NClist::from_vec(v).unwrap();
+        for q in (0..120).map(|s| s..s + 5) {
+            assert_eq!(list.count_overlaps(&q), nclist.count_overlaps(&q));
+            assert_eq!(list.overlaps(&q).count(), nclist.count_overlaps(&q));
+            assert!(list.overlaps_ordered(&q).eq(nclist.overlaps_ordered(&q)));
+        }
+
+        list.compact();
+        assert_eq!(list.levels.len(), 1);
+        assert_eq!(list.len(), nclist.len());
+    }
+}
@@ -0,0 +1,72 @@
+//! The error returned when a collection of intervals cannot be indexed.
+use std::error;
+use std::fmt;
+
+use crate::query;
+use crate::Interval;
+
+/// Error returned when creating an `NClist` (or one of the other collections) fails. The
+/// rejected intervals are returned in the error, in the order they were provided.
+#[derive(Debug, Clone, PartialEq)]
+#[non_exhaustive]
+pub enum Error<V> {
+    /// The interval at `index` is not valid for its `Semantics`, e.g. the end coordinate is
+    /// before the start coordinate.
+    InvalidInterval { index: usize, intervals: Vec<V> },
+    /// The interval at `index` sorts before the previous interval, while sorted input was
+    /// required.
+    Unsorted { index: usize, intervals: Vec<V> },
+}
+
+impl<V> Error<V> {
+    /// Returns the position of the offending interval.
+    pub fn index(&self) -> usize {
+        match *self {
+            Error::InvalidInterval { index, .. } | Error::Unsorted { index, .. } => index,
+        }
+    }
+
+    /// Returns the offending interval.
+    pub fn interval(&self) -> &V {
+        &self.intervals()[self.index()]
+    }
+
+    /// Returns the rejected intervals.
+    pub fn intervals(&self) -> &[V] {
+        match self {
+            Error::InvalidInterval { intervals, .. } | Error::Unsorted { intervals, .. } => intervals,
+        }
+    }
+
+    /// Returns the rejected intervals, giving back ownership of the input.
+    pub fn into_vec(self) -> Vec<V> {
+        match self {
+            Error::InvalidInterval { intervals, .. } | Error::Unsorted { intervals, .. } => intervals,
+        }
+    }
+
+    /// Returns `v` if the interval `f` returns for every element is valid for its `Semantics`.
+    pub(crate) fn check<T, F>(v: Vec<V>, f: F) -> Result<Vec<V>, Error<V>>
+        where T: Interval, F: Fn(&V) -> &T
+    {
+        match v.iter().position(|e| !query::is_valid(f(e))) {
+            Some(index) => Err(Error::InvalidInterval { index, intervals: v }),
+            None => Ok(v),
+        }
+    }
+}
+
+impl<V: fmt::Debug> fmt::Display for Error<V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::InvalidInterval { index, .. } => {
+                write!(f, "Invalid interval at index {}: {:?}", index, self.interval())
+            }
+            Error::Unsorted { index, .. } => {
+                write!(f, "Interval at index {} is out of order: {:?}", index, self.interval())
+            }
+        }
+    }
+}
+
+impl<V: fmt::Debug> error::Error for Error<V> {}
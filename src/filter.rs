@@ -0,0 +1,152 @@
+//! Overlap queries with a minimum overlap size.
+//!
+//! Computing the size of an overlap requires subtracting coordinates, which is not needed for
+//! any other query. The `Length` trait collects the required bounds and is implemented for every
+//! coordinate type that supports them, the filtered queries are only available for these types.
+//!
+//! The length of an element follows its `Semantics`. A closed element includes its end
+//! coordinate, so `5..=5` has length 1. An empty element (`Semantics::AllowEmpty`) is a single
+//! position with length 0, it matches `MinOverlap::Length(0)` and every fraction of itself.
+use std::ops::{Add, Range, Sub};
+
+use num_traits::{One, ToPrimitive};
+
+use crate::{Interval, NClist, Overlaps, Semantics};
+
+/// Coordinates that can be subtracted to compute the length of an interval or overlap. This is
+/// implemented for all types that are `Ord + Clone + Add + Sub + One + ToPrimitive`, like the
+/// primitive integers.
+pub trait Length: Ord + Clone + Add<Output = Self> + Sub<Output = Self> + One + ToPrimitive {}
+
+impl<C> Length for C where C: Ord + Clone + Add<Output = C> + Sub<Output = C> + One + ToPrimitive {}
+
+/// The minimum overlap between an element and the query (like the `-f`, `-F`, `-r` and `-e`
+/// options of bedtools).
+#[derive(Debug, Clone, PartialEq)]
+pub enum MinOverlap<C> {
+    /// The overlap is at least this number of coordinates.
+    Length(C),
+    /// The overlap is at least this fraction of the query.
+    QueryFraction(f64),
+    /// The overlap is at least this fraction of the element.
+    ElementFraction(f64),
+    /// The overlap is at least this fraction of both the query and the element.
+    Reciprocal(f64),
+    /// The overlap is at least this fraction of the query or the element.
+    Either(f64),
+}
+
+/// Iterator over the elements overlapping a query by at least a minimum overlap, created by
+/// `NClist::overlaps_min`.
+pub struct OverlapsMin<'a, T> where T: 'a + Interval {
+    overlaps: Overlaps<'a, T>,
+    range: &'a Range<T::Coord>,
+    min: MinOverlap<T::Coord>,
+}
+
+impl<C> MinOverlap<C> where C: Length {
+    /// Returns `true` if element `e` overlaps `r` by at least the minimum overlap.
+    pub fn matches<T>(&self, e: &T, r: &Range<C>) -> bool where T: Interval<Coord = C> {
+        if r.end <= r.start {
+            return false;
+        }
+        let (overlap, length) = match T::SEMANTICS {
+            Semantics::Closed => {
+                let last = r.end.clone() - C::one();
+                let start = e.start().max(&r.start);
+                let end = e.end().min(&last);
+                if end < start {
+                    return false;
+                }
+                (end.clone() - start.clone() + C::one(), e.end().clone() - e.start().clone() + C::one())
+            },
+            //an empty element is a position in the query without length
+            Semantics::AllowEmpty if e.start() == e.end() => {
+                if *e.start() < r.start || *e.start() >= r.end {
+                    return false;
+                }
+                let zero = e.end().clone() - e.start().clone();
+                (zero.clone(), zero)
+            },
+            _ => {
+                let start = e.start().max(&r.start);
+                let end = e.end().min(&r.end);
+                if end <= start {
+                    return false;
+                }
+                (end.clone() - start.clone(), e.end().clone() - e.start().clone())
+            },
+        };
+        let fraction = |length: &C, f: f64| {
+            match (overlap.to_f64(), length.to_f64()) {
+                (Some(overlap), Some(length)) => overlap >= f * length,
+                _ => false,
+            }
+        };
+        let query_length = r.end.clone() - r.start.clone();
+        match *self {
+            MinOverlap::Length(ref n) => overlap >= *n,
+            MinOverlap::QueryFraction(f) => fraction(&query_length, f),
+            MinOverlap::ElementFraction(f) => fraction(&length, f),
+            MinOverlap::Reciprocal(f) => fraction(&query_length, f) && fraction(&length, f),
+            MinOverlap::Either(f) => fraction(&query_length, f) || fraction(&length, f),
+        }
+    }
+}
+
+impl<T> NClist<T> where T: Interval, T::Coord: Length {
+    /// Returns an iterator over the elements overlapping the `Range` r by at least `min`.
+    pub fn overlaps_min<'a>(&'a self, r: &'a Range<T::Coord>, min: MinOverlap<T::Coord>) -> OverlapsMin<'a, T> {
+        OverlapsMin { overlaps: self.overlaps(r), range: r, min }
+    }
+
+    /// Count the number of elements overlapping the `Range` r by at least `min`.
+    pub fn count_overlaps_min(&self, r: &Range<T::Coord>, min: MinOverlap<T::Coord>) -> usize {
+        self.overlaps(r).filter(|e| min.matches(*e, r)).count()
+    }
+}
+
+impl<'a, T> Iterator for OverlapsMin<'a, T> where T: Interval, T::Coord: Length {
+    type Item = &'a T;
+    fn next(&mut self) -> Option<Self::Item> {
+        let (range, min) = (self.range, &self.min);
+        self.overlaps.find(|e| min.matches(*e, range))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MinOverlap;
+    use crate::tests::Site;
+    use crate::NClist;
+
+    #[test]
+    fn min_overlap() {
+        let nclist = NClist::from_vec(vec![(0u32..100), (10..20), (18..22), (25..26)]).unwrap();
+        let q = 15..25;
+        let found = |min| nclist.overlaps_min(&q, min).cloned().collect::<Vec<_>>();
+        assert_eq!(found(MinOverlap::Length(4)), vec![0..100, 10..20, 18..22]);
+        assert_eq!(found(MinOverlap::Length(5)), vec![0..100, 10..20]);
+        assert_eq!(found(MinOverlap::QueryFraction(0.5)), vec![0..100, 10..20]);
+        assert_eq!(found(MinOverlap::ElementFraction(0.5)), vec![10..20, 18..22]);
+        assert_eq!(found(MinOverlap::Reciprocal(0.5)), vec![10..20]);
+        assert_eq!(found(MinOverlap::Either(0.9)), vec![0..100, 18..22]);
+        assert_eq!(nclist.count_overlaps_min(&q, MinOverlap::Length(1)), 3);
+        assert_eq!(nclist.count_overlaps_min(&(20..20), MinOverlap::Length(0)), 0);
+    }
+
+    #[test]
+    fn closed_and_empty() {
+        let nclist = NClist::from_vec(vec![(5u32..=5), (3..=8), (8..=9)]).unwrap();
+        assert_eq!(nclist.count_overlaps_min(&(5..6), MinOverlap::Length(1)), 2);
+        assert_eq!(nclist.count_overlaps_min(&(0..10), MinOverlap::Length(6)), 1);
+        assert_eq!(nclist.count_overlaps_min(&(0..10), MinOverlap::Length(7)), 0);
+        assert_eq!(nclist.count_overlaps_min(&(8..9), MinOverlap::ElementFraction(0.5)), 1);
+        assert_eq!(nclist.count_overlaps_min(&(5..6), MinOverlap::QueryFraction(1.0)), 2);
+
+        let sites = NClist::from_vec(vec![Site(5..5), Site(3..8)]).unwrap();
+        assert_eq!(sites.count_overlaps_min(&(4..6), MinOverlap::Length(0)), 2);
+        assert_eq!(sites.count_overlaps_min(&(4..6), MinOverlap::Length(1)), 1);
+        assert_eq!(sites.count_overlaps_min(&(4..6), MinOverlap::ElementFraction(1.0)), 1);
+    }
+}
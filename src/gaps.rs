@@ -0,0 +1,87 @@
+//! The uncovered parts of a bounding range (the complement of an `NClist`).
+//!
+//! Only the top-level list needs to be visited, contained intervals never cover a position that is
+//! not already covered by their parent.
+use std::ops::Range;
+
+use crate::{Interval, NClist};
+
+/// Iterator over the gaps between the elements of an `NClist`, created by `NClist::gaps`.
+pub struct Gaps<'a, T> where T: 'a + Interval {
+    nclist: &'a NClist<T>,
+    range: &'a Range<T::Coord>,
+    pos: usize,
+    end: usize,
+    covered_to: &'a T::Coord,
+}
+
+impl<T> NClist<T> where T: Interval {
+    /// Returns an iterator over the half-open ranges within `r` that are not covered by any
+    /// element, ordered by start coordinate.
+    pub fn gaps<'a>(&'a self, r: &'a Range<T::Coord>) -> Gaps<'a, T> {
+        let &(start, end) = self.contained[0].as_ref().unwrap();
+        let pos = start + self.bin_search_end(start, end, &r.start);
+        Gaps { nclist: self, range: r, pos, end, covered_to: &r.start }
+    }
+}
+
+impl<'a, T> Iterator for Gaps<'a, T> where T: Interval, T::Coord: Clone {
+    type Item = Range<T::Coord>;
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.covered_to < &self.range.end {
+            let gap_start = self.covered_to;
+            let next = self.nclist.intervals[self.pos..self.end].first();
+            match next {
+                Some(e) if e.start() < &self.range.end => {
+                    self.pos += 1;
+                    //empty elements do not cover any position
+                    if e.start() == e.end() {
+                        continue;
+                    }
+                    //top-level elements are sorted on end coordinate
+                    self.covered_to = e.end();
+                    if e.start() > gap_start {
+                        return Some(gap_start.clone()..e.start().clone());
+                    }
+                }
+                _ => {
+                    self.covered_to = &self.range.end;
+                    return Some(gap_start.clone()..self.range.end.clone());
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::Site;
+    use crate::NClist;
+
+    #[test]
+    fn gaps() {
+        let nclist = NClist::from_vec(vec![(5u32..10), (6..8), (8..12), (15..20), (16..17), (25..30)]).unwrap();
+        let gaps: Vec<_> = nclist.gaps(&(0..40)).collect();
+        assert_eq!(gaps, vec![0..5, 12..15, 20..25, 30..40]);
+        let gaps: Vec<_> = nclist.gaps(&(7..27)).collect();
+        assert_eq!(gaps, vec![12..15, 20..25]);
+        let gaps: Vec<_> = nclist.gaps(&(16..18)).collect();
+        assert_eq!(gaps, vec![]);
+        let gaps: Vec<_> = nclist.gaps(&(21..24)).collect();
+        assert_eq!(gaps, vec![21..24]);
+        assert_eq!(nclist.gaps(&(10..10)).count(), 0);
+
+        let empty: NClist<std::ops::Range<u32>> = NClist::from_vec(Vec::new()).unwrap();
+        assert_eq!(empty.gaps(&(0..100)).collect::<Vec<_>>(), vec![0..100]);
+    }
+
+    #[test]
+    fn empty_elements() {
+        let nclist = NClist::from_vec(vec![Site(0..2), Site(5..5), Site(8..9)]).unwrap();
+        let gaps: Vec<_> = nclist.gaps(&(0..10)).collect();
+        assert_eq!(gaps, vec![2..8, 9..10]);
+        let gaps: Vec<_> = nclist.gaps(&(5..6)).collect();
+        assert_eq!(gaps, vec![5..6]);
+    }
+}
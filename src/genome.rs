@@ -0,0 +1,202 @@
+//! An index of intervals on multiple sequences, for example the chromosomes of a genome.
+//!
+//! A `GenomeNClist<K, T>` stores one `NClist<T>` per sequence name `K`. The sequences are kept in
+//! the order of their first appearance in the input, which for sorted annotation files is the
+//! order of the file. Queries on a sequence that is not present in the index return an
+//! `UnknownSequence` error.
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::hash::Hash;
+
+use crate::{Interval, Iter, NClist, OrderedOverlaps, Overlaps, Query};
+
+/// Error returned when querying a sequence name that is not present in a `GenomeNClist`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownSequence<K>(pub K);
+
+impl<K: fmt::Debug> fmt::Display for UnknownSequence<K> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Unknown sequence {:?}", self.0)
+    }
+}
+
+impl<K: fmt::Debug> Error for UnknownSequence<K> {}
+
+/// A collection of `NClist<T>`, one for every sequence name `K`.
+#[derive(Debug)]
+pub struct GenomeNClist<K, T> where T: Interval {
+    sequences: Vec<(K, NClist<T>)>,
+    index: HashMap<K, usize>,
+}
+
+pub struct GenomeIter<'a, K, T> where T: 'a + Interval {
+    sequences: std::slice::Iter<'a, (K, NClist<T>)>,
+    current: Option<(&'a K, Iter<'a, T>)>,
+}
+
+impl<K, T> GenomeNClist<K, T> where K: Hash + Eq + Clone, T: Interval {
+    /// Create a `GenomeNClist` from `(sequence, interval)` pairs. The same validation as
+    /// `NClist::from_vec` is applied to the intervals.
+    pub fn from_vec(v: Vec<(K, T)>) -> Result<GenomeNClist<K, T>, crate::Error<(K, T)>> {
+        let v = crate::Error::check(v, |(_, e)| e)?;
+        Ok(GenomeNClist::from_iter_with_key(v))
+    }
+
+    /// Create a `GenomeNClist` from intervals that contain their sequence name. The function `f`
+    /// returns the sequence name of an interval.
+    pub fn from_vec_by_key<F>(v: Vec<T>, mut f: F) -> Result<GenomeNClist<K, T>, crate::Error<T>>
+        where F: FnMut(&T) -> K
+    {
+        let v = crate::Error::check(v, |e| e)?;
+        Ok(GenomeNClist::from_iter_with_key(v.into_iter().map(|e| (f(&e), e))))
+    }
+
+    /// Group valid intervals by sequence name.
+    fn from_iter_with_key<I>(it: I) -> GenomeNClist<K, T>
+        where I: IntoIterator<Item = (K, T)>
+    {
+        let mut index = HashMap::new();
+        let mut grouped: Vec<(K, Vec<T>)> = Vec::new();
+        for (k, e) in it {
+            let i = *index.entry(k).or_insert_with_key(|k| {
+                grouped.push((k.clone(), Vec::new()));
+                grouped.len() - 1
+            });
+            grouped[i].1.push(e);
+        }
+
+        let sequences = grouped.into_iter()
+            .map(|(k, v)| (k, NClist::build(v)))
+            .collect();
+        GenomeNClist { sequences, index }
+    }
+}
+
+impl<K, T> GenomeNClist<K, T> where K: Hash + Eq, T: Interval {
+    /// Returns the `NClist` of sequence `seq`.
+    pub fn get<Q>(&self, seq: &Q) -> Option<&NClist<T>>
+        where K: Borrow<Q>, Q: Hash + Eq + ?Sized
+    {
+        self.index.get(seq).map(|&i| &self.sequences[i].1)
+    }
+
+    /// Returns an iterator over the sequence names in order of appearance.
+    pub fn sequences(&self) -> impl Iterator<Item = &K> {
+        self.sequences.iter().map(|(k, _)| k)
+    }
+
+    /// Returns the total number of intervals on all sequences.
+    pub fn len(&self) -> usize {
+        self.sequences.iter().map(|(_, nclist)| nclist.len()).sum()
+    }
+
+    /// Returns `true` if there are no intervals.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Count the number of elements on sequence `seq` overlapping query `r`.
+    pub fn count_overlaps<Q, R>(&self, seq: &Q, r: &R) -> Result<usize, UnknownSequence<K>>
+        where K: Borrow<Q>, Q: Hash + Eq + ToOwned<Owned = K> + ?Sized, R: Query<T::Coord> + ?Sized
+    {
+        self.lookup(seq).map(|nclist| nclist.count_overlaps(r))
+    }
+
+    /// Returns an iterator that returns elements on sequence `seq` overlapping query `r`.
+    pub fn overlaps<'a, Q, R>(&'a self, seq: &Q, r: &'a R) -> Result<Overlaps<'a, T>, UnknownSequence<K>>
+        where K: Borrow<Q>, Q: Hash + Eq + ToOwned<Owned = K> + ?Sized, R: Query<T::Coord> + ?Sized
+    {
+        self.lookup(seq).map(|nclist| nclist.overlaps(r))
+    }
+
+    /// Returns an iterator that returns elements on sequence `seq` overlapping query `r` ordered
+    /// by start coordinate.
+    pub fn overlaps_ordered<'a, Q, R>(&'a self, seq: &Q, r: &'a R) -> Result<OrderedOverlaps<'a, T>, UnknownSequence<K>>
+        where K: Borrow<Q>, Q: Hash + Eq + ToOwned<Owned = K> + ?Sized, R: Query<T::Coord> + ?Sized
+    {
+        self.lookup(seq).map(|nclist| nclist.overlaps_ordered(r))
+    }
+
+    /// Returns an iterator over all `(sequence, interval)` pairs. The sequences are returned in
+    /// order of appearance and the intervals on a sequence are ordered by start coordinate.
+    pub fn iter(&self) -> GenomeIter<'_, K, T> {
+        GenomeIter { sequences: self.sequences.iter(), current: None }
+    }
+
+    fn lookup<Q>(&self, seq: &Q) -> Result<&NClist<T>, UnknownSequence<K>>
+        where K: Borrow<Q>, Q: Hash + Eq + ToOwned<Owned = K> + ?Sized
+    {
+        self.get(seq).ok_or_else(|| UnknownSequence(seq.to_owned()))
+    }
+}
+
+impl<'a, K, T> Iterator for GenomeIter<'a, K, T> where T: Interval {
+    type Item = (&'a K, &'a T);
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((k, ref mut it)) = self.current {
+                if let Some(e) = it.next() {
+                    return Some((k, e));
+                }
+            }
+            let (k, nclist) = self.sequences.next()?;
+            self.current = Some((k, nclist.iter()));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ops::Range;
+
+    fn genome() -> GenomeNClist<String, Range<u64>> {
+        let v = vec![("chr2", 10..20), ("chr1", 5..8), ("chr2", 1..5), ("chr2", 12..14), ("chr1", 1..10)];
+        GenomeNClist::from_vec(v.into_iter().map(|(k, r)| (k.to_string(), r)).collect()).unwrap()
+    }
+
+    #[test]
+    fn query() {
+        let genome = genome();
+        assert_eq!(genome.len(), 5);
+        assert_eq!(genome.count_overlaps("chr2", &(4..13)), Ok(3));
+        assert_eq!(genome.count_overlaps("chr1", &(4..13)), Ok(2));
+        assert_eq!(genome.overlaps("chr1", &(8..9)).unwrap().collect::<Vec<_>>(), vec![&(1..10)]);
+        assert_eq!(genome.count_overlaps("chr3", &(4..13)), Err(UnknownSequence("chr3".to_string())));
+        assert!(genome.overlaps("chrX", &(4..13)).is_err());
+
+        let err = GenomeNClist::from_vec(vec![("chr1", 1..5), ("chr1", 5..5)]).unwrap_err();
+        assert_eq!(err.interval(), &("chr1", 5..5));
+    }
+
+    #[test]
+    fn iter() {
+        let genome = genome();
+        assert_eq!(genome.sequences().collect::<Vec<_>>(), vec!["chr2", "chr1"]);
+        let v: Vec<_> = genome.iter().map(|(k, r)| (k.as_str(), r.clone())).collect();
+        assert_eq!(v, vec![("chr2", 1..5), ("chr2", 10..20), ("chr2", 12..14), ("chr1", 1..10), ("chr1", 5..8)]);
+    }
+
+    #[derive(Debug)]
+    struct Feature(u8, Range<u64>);
+
+    impl Interval for Feature {
+        type Coord = u64;
+        fn start(&self) -> &u64 {
+            &self.1.start
+        }
+        fn end(&self) -> &u64 {
+            &self.1.end
+        }
+    }
+
+    #[test]
+    fn by_key() {
+        let v = vec![Feature(1, 10..20), Feature(2, 5..8), Feature(1, 1..5)];
+        let genome = GenomeNClist::from_vec_by_key(v, |e| e.0).unwrap();
+        assert_eq!(genome.get(&1).map(NClist::len), Some(2));
+        assert_eq!(genome.count_overlaps(&2, &(0..100)), Ok(1));
+    }
+}
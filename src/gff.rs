@@ -0,0 +1,310 @@
+//! Reader for GFF3 and GTF files.
+//!
+//! GFF3 and GTF use 1-based closed coordinates. These are converted to 0-based half-open
+//! coordinates when reading, so a `GffRecord` can directly be used as an `Interval`. Features
+//! with an end coordinate before the start coordinate would have zero or negative width and are
+//! reported as an error.
+//!
+//! Comment and directive lines are skipped. For GFF3 reading stops at a `##FASTA` directive.
+//!
+//! # Example
+//! ```
+//! use nclist::NClist;
+//! use nclist::gff::{Format, Reader};
+//!
+//! let data = "chr1\tsrc\tgene\t11\t20\t.\t+\t.\tID=gene1;Name=ABC\n\
+//!             chr1\tsrc\texon\t11\t14\t.\t+\t.\tParent=gene1\n";
+//! let records = Reader::new(data.as_bytes(), Format::Gff3).collect::<Result<Vec<_>, _>>().unwrap();
+//! assert_eq!(records[0].start, 10);
+//! assert_eq!(records[0].attribute("Name"), Some("ABC"));
+//!
+//! let nclist = NClist::from_vec(records).unwrap();
+//! assert_eq!(nclist.count_overlaps(&(13..15)), 2);
+//! ```
+use std::error::Error as StdError;
+use std::fmt;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::{Interval, Strand, Stranded};
+
+/// The flavour of the GFF file, this determines how the attribute column is parsed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// GFF3, attributes are `key=value` pairs separated by `;` with percent-encoding.
+    Gff3,
+    /// GTF (GFF2), attributes are `key "value"` pairs separated by `;`.
+    Gtf,
+}
+
+/// A single GFF3 or GTF feature. The coordinates are converted to 0-based half-open. Optional
+/// fields are `None` when the column contains `.`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GffRecord {
+    pub seqid: String,
+    pub source: Option<String>,
+    pub feature_type: String,
+    pub start: u64,
+    pub end: u64,
+    pub score: Option<f64>,
+    pub strand: Strand,
+    pub phase: Option<u8>,
+    /// The attributes in order of appearance.
+    pub attributes: Vec<(String, String)>,
+}
+
+/// Errors that can occur while reading a GFF3 or GTF file.
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Parse { line: usize, message: String },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "I/O error reading GFF: {}", e),
+            Error::Parse { line, message } => write!(f, "Invalid GFF record on line {}: {}", line, message),
+        }
+    }
+}
+
+impl StdError for Error {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Parse { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error::Io(e)
+    }
+}
+
+impl GffRecord {
+    /// Returns the value of the first attribute named `key`.
+    pub fn attribute(&self, key: &str) -> Option<&str> {
+        self.attributes.iter().find(|(k, _)| k == key).map(|(_, v)| v.as_str())
+    }
+}
+
+impl Interval for GffRecord {
+    type Coord = u64;
+
+    #[inline(always)]
+    fn start(&self) -> &u64 {
+        &self.start
+    }
+
+    #[inline(always)]
+    fn end(&self) -> &u64 {
+        &self.end
+    }
+}
+
+impl Stranded for GffRecord {
+    #[inline(always)]
+    fn strand(&self) -> Strand {
+        self.strand
+    }
+}
+
+/// An iterator over the records in a GFF3 or GTF file.
+pub struct Reader<R> {
+    inner: R,
+    format: Format,
+    line: usize,
+    buf: String,
+}
+
+impl Reader<BufReader<File>> {
+    /// Open the GFF3 or GTF file at `path`.
+    pub fn from_path<P: AsRef<Path>>(path: P, format: Format) -> io::Result<Reader<BufReader<File>>> {
+        File::open(path).map(|f| Reader::new(BufReader::new(f), format))
+    }
+}
+
+impl<R> Reader<R> where R: BufRead {
+    /// Create a reader that reads records in `format` from `inner`.
+    pub fn new(inner: R, format: Format) -> Reader<R> {
+        Reader { inner, format, line: 0, buf: String::new() }
+    }
+}
+
+impl<R> Iterator for Reader<R> where R: BufRead {
+    type Item = Result<GffRecord, Error>;
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            self.buf.clear();
+            match self.inner.read_line(&mut self.buf) {
+                Ok(0) => return None,
+                Ok(_) => self.line += 1,
+                Err(e) => return Some(Err(e.into())),
+            }
+            let line = self.buf.trim_end_matches(&['\n', '\r'][..]);
+            if self.format == Format::Gff3 && line.starts_with("##FASTA") {
+                return None;
+            }
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            return Some(parse_record(line, self.format).map_err(|message| Error::Parse { line: self.line, message }));
+        }
+    }
+}
+
+fn parse_record(line: &str, format: Format) -> Result<GffRecord, String> {
+    let fields: Vec<&str> = line.split('\t').collect();
+    if fields.len() < 8 || fields.len() > 9 {
+        return Err(format!("expected 8 or 9 columns, found {}", fields.len()));
+    }
+    let optional = |i: usize| Some(fields[i]).filter(|f| *f != ".");
+
+    let start: u64 = parse_field(fields[3], "start")?;
+    let end: u64 = parse_field(fields[4], "end")?;
+    if start == 0 {
+        return Err("start must be 1 or larger".to_string());
+    }
+    if end < start {
+        return Err(format!("feature {}-{} has zero or negative width", start, end));
+    }
+
+    let strand = match optional(6) {
+        None | Some("?") => Strand::Unknown,
+        Some("+") => Strand::Forward,
+        Some("-") => Strand::Reverse,
+        Some(s) => return Err(format!("invalid strand '{}'", s)),
+    };
+    let phase = match optional(7) {
+        None => None,
+        Some(p @ "0") | Some(p @ "1") | Some(p @ "2") => Some(p.parse().unwrap()),
+        Some(p) => return Err(format!("invalid phase '{}'", p)),
+    };
+
+    let attributes = match fields.get(8).copied().filter(|f| *f != ".") {
+        None => Vec::new(),
+        Some(a) => match format {
+            Format::Gff3 => parse_gff3_attributes(a)?,
+            Format::Gtf => parse_gtf_attributes(a)?,
+        },
+    };
+
+    Ok(GffRecord {
+        seqid: fields[0].to_string(),
+        source: optional(1).map(str::to_string),
+        feature_type: fields[2].to_string(),
+        start: start - 1,
+        end,
+        score: optional(5).map(|s| parse_field(s, "score")).transpose()?,
+        strand,
+        phase,
+        attributes,
+    })
+}
+
+fn parse_field<N: FromStr>(s: &str, name: &str) -> Result<N, String> {
+    s.trim().parse().map_err(|_| format!("invalid {} '{}'", name, s))
+}
+
+fn parse_gff3_attributes(s: &str) -> Result<Vec<(String, String)>, String> {
+    s.split(';')
+        .filter(|a| !a.trim().is_empty())
+        .map(|a| {
+            let mut kv = a.splitn(2, '=');
+            match (kv.next(), kv.next()) {
+                (Some(k), Some(v)) => Ok((percent_decode(k.trim())?, percent_decode(v)?)),
+                _ => Err(format!("invalid attribute '{}'", a)),
+            }
+        })
+        .collect()
+}
+
+fn parse_gtf_attributes(s: &str) -> Result<Vec<(String, String)>, String> {
+    s.split(';')
+        .map(str::trim)
+        .filter(|a| !a.is_empty())
+        .map(|a| {
+            let mut kv = a.splitn(2, char::is_whitespace);
+            match (kv.next(), kv.next()) {
+                (Some(k), Some(v)) => Ok((k.to_string(), v.trim().trim_matches('"').to_string())),
+                _ => Err(format!("invalid attribute '{}'", a)),
+            }
+        })
+        .collect()
+}
+
+fn percent_decode(s: &str) -> Result<String, String> {
+    if !s.contains('%') {
+        return Ok(s.to_string());
+    }
+    let mut bytes = Vec::with_capacity(s.len());
+    let mut it = s.bytes();
+    while let Some(b) = it.next() {
+        if b == b'%' {
+            let hex: Vec<u8> = it.by_ref().take(2).collect();
+            let decoded = std::str::from_utf8(&hex).ok()
+                .filter(|h| h.len() == 2)
+                .and_then(|h| u8::from_str_radix(h, 16).ok())
+                .ok_or_else(|| format!("invalid percent-encoding in '{}'", s))?;
+            bytes.push(decoded);
+        } else {
+            bytes.push(b);
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| format!("invalid percent-encoding in '{}'", s))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_gff3() {
+        let data = "##gff-version 3\n\
+                    chr1\tsrc\tgene\t1\t100\t.\t+\t.\tID=g1;Note=a%3Bb,c\n\
+                    chr1\t.\tCDS\t10\t10\t0.5\t-\t2\tParent=g1\n\
+                    ##FASTA\n>chr1\nACGT\n";
+        let records: Vec<_> = Reader::new(data.as_bytes(), Format::Gff3).collect::<Result<_, _>>().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!((records[0].start, records[0].end), (0, 100));
+        assert_eq!(records[0].source.as_deref(), Some("src"));
+        assert_eq!(records[0].attribute("Note"), Some("a;b,c"));
+        assert_eq!((records[1].start, records[1].end), (9, 10));
+        assert_eq!(records[1].source, None);
+        assert_eq!(records[1].score, Some(0.5));
+        assert_eq!(records[1].strand, Strand::Reverse);
+        assert_eq!(records[1].phase, Some(2));
+    }
+
+    #[test]
+    fn read_gtf() {
+        let data = "chr1\tsrc\texon\t5\t20\t.\t-\t.\tgene_id \"g1\"; transcript_id \"t1\"; tag \"basic\"; tag \"CCDS\";\n";
+        let records: Vec<_> = Reader::new(data.as_bytes(), Format::Gtf).collect::<Result<_, _>>().unwrap();
+        assert_eq!((records[0].start, records[0].end), (4, 20));
+        assert_eq!(records[0].attribute("transcript_id"), Some("t1"));
+        assert_eq!(records[0].attributes.len(), 4);
+        assert_eq!(records[0].attributes[3], ("tag".to_string(), "CCDS".to_string()));
+    }
+
+    #[test]
+    fn errors() {
+        let lines = [
+            "chr1\tsrc\tgene\t10\t9\t.\t+\t.\tID=g1",
+            "chr1\tsrc\tgene\t0\t9\t.\t+\t.\tID=g1",
+            "chr1\tsrc\tgene\t1\t9\t.\tx\t.\tID=g1",
+            "chr1\tsrc\tgene\t1\t9\t.\t+\t3\tID=g1",
+            "chr1\tsrc\tgene\t1\t9",
+            "chr1\tsrc\tgene\t1\t9\t.\t+\t.\tID",
+        ];
+        for line in &lines {
+            let data = format!("#comment\n{}\n", line);
+            let err = Reader::new(data.as_bytes(), Format::Gff3).next().unwrap().unwrap_err();
+            assert!(matches!(err, Error::Parse { line: 2, .. }), "{}", line);
+        }
+    }
+}
@@ -0,0 +1,105 @@
+//! Overlap join of two interval sets.
+//!
+//! The intervals of the left side are visited in order of their start coordinate and used as
+//! sorted queries on the right `NClist`. The sublist positions on the right side only move
+//! forward (see `NClist::overlaps_many`), so both sets are traversed once apart from the
+//! overlapping pairs that are returned.
+use crate::batch::Sweep;
+use crate::query::Query;
+use crate::{Interval, Iter, NClist};
+
+/// Iterator over all overlapping pairs of two interval sets, created by `NClist::join` or
+/// `NClist::join_sorted`.
+pub struct Join<'a, A, B, I> where B: 'a + Interval {
+    left: I,
+    sweep: Sweep<'a, B>,
+    current: Option<&'a A>,
+    matches: Vec<&'a B>,
+    pos: usize,
+}
+
+impl<T> NClist<T> where T: Interval {
+    /// Returns an iterator over all pairs `(a, b)` where `a` from this `NClist` overlaps `b` from
+    /// `other`. The pairs are ordered by the start coordinate of `a`.
+    pub fn join<'a, U>(&'a self, other: &'a NClist<U>) -> Join<'a, T, U, Iter<'a, T>>
+        where U: Interval<Coord = T::Coord>
+    {
+        other.join_sorted(self.iter())
+    }
+
+    /// Returns an iterator over all pairs `(a, b)` where `a` from `left` overlaps `b` from this
+    /// `NClist`. The join is most efficient when `left` is sorted by start coordinate, unsorted
+    /// input returns the same pairs.
+    pub fn join_sorted<'a, A, I>(&'a self, left: I) -> Join<'a, A, T, I::IntoIter>
+        where A: 'a + Interval<Coord = T::Coord>, I: IntoIterator<Item = &'a A>
+    {
+        Join { left: left.into_iter(), sweep: Sweep::new(self), current: None, matches: Vec::new(), pos: 0 }
+    }
+}
+
+impl<'a, A, B, I> Iterator for Join<'a, A, B, I>
+    where A: Interval<Coord = B::Coord>, B: Interval, I: Iterator<Item = &'a A>
+{
+    type Item = (&'a A, &'a B);
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(a) = self.current {
+                if let Some(&b) = self.matches.get(self.pos) {
+                    self.pos += 1;
+                    return Some((a, b));
+                }
+            }
+
+            let a = self.left.next()?;
+            self.current = Some(a);
+            self.matches.clear();
+            self.pos = 0;
+            let matches = &mut self.matches;
+            self.sweep.query(a.lower(), a.upper(), |b| matches.push(b));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{random_intervals, Site};
+    use crate::NClist;
+
+    #[test]
+    fn join() {
+        let peaks = NClist::from_vec(vec![(5u32..12), (30..31), (40..60), (45..50)]).unwrap();
+        let promoters = NClist::from_vec(vec![(0u32..10), (10..20), (48..52), (100..200)]).unwrap();
+        let pairs: Vec<_> = peaks.join(&promoters).map(|(a, b)| (a.clone(), b.clone())).collect();
+        assert_eq!(pairs, vec![(5..12, 0..10), (5..12, 10..20), (40..60, 48..52), (45..50, 48..52)]);
+
+        let queries = vec![11..12, 49..50, 150..151];
+        assert_eq!(promoters.join_sorted(&queries).count(), 3);
+    }
+
+    #[test]
+    fn closed_and_empty() {
+        let left = NClist::from_vec(vec![(1u32..=5), (9..=9)]).unwrap();
+        let right = NClist::from_vec(vec![(5u32..=8), (9..=12)]).unwrap();
+        let pairs: Vec<_> = left.join(&right).map(|(a, b)| (a.clone(), b.clone())).collect();
+        assert_eq!(pairs, vec![(1..=5, 5..=8), (9..=9, 9..=12)]);
+        assert_eq!(pairs.len(), left.iter().map(|a| right.count_overlaps(a)).sum::<usize>());
+
+        let sites = NClist::from_vec(vec![Site(5..5), Site(8..8), Site(20..20)]).unwrap();
+        let regions = NClist::from_vec(vec![Site(3..8), Site(8..10)]).unwrap();
+        let pairs: Vec<_> = sites.join(&regions).map(|(a, b)| (a.0.clone(), b.0.clone())).collect();
+        assert_eq!(pairs, vec![(5..5, 3..8), (8..8, 8..10)]);
+        assert_eq!(pairs.len(), sites.iter().map(|a| regions.count_overlaps(a)).sum::<usize>());
+    }
+
+    #[test]
+    fn matches_nested_loop() {
+        let a = random_intervals(200, 500, 40);
+        let b = random_intervals(300, 520, 25);
+        let expected = a.iter().map(|x| b.iter().filter(|y| x.start < y.end && y.start < x.end).count()).sum::<usize>();
+
+        let left = NClist::from_vec(a).unwrap();
+        let right = NClist::from_vec(b).unwrap();
+        assert_eq!(left.join(&right).count(), expected);
+        assert!(left.join(&right).all(|(x, y)| x.start < y.end && y.start < x.end));
+    }
+}
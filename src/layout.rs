@@ -0,0 +1,95 @@
+//! Validation of a stored `NClist` layout.
+//!
+//! A list that is loaded instead of built (see the `serde` feature and `archive::ArchivedNClist`)
+//! is only queried correctly when the nesting invariants of `build_nclist` hold. Every interval
+//! must be in exactly one sublist, a sublist must be sorted on start and end coordinate, and the
+//! intervals in a sublist must be contained in the interval that links to it.
+use std::collections::VecDeque;
+
+use crate::query;
+use crate::{Interval, Layout};
+
+/// Check that `intervals` with `n_sublists` entries in the `contained` storage of `list` is a
+/// valid `NClist` layout.
+pub(crate) fn check<L: Layout>(list: &L, intervals: &[L::Item], n_sublists: usize) -> Result<(), &'static str> {
+    let n = intervals.len();
+    if n_sublists != n + 1 {
+        return Err("The number of sublists does not match the number of intervals");
+    }
+    if !intervals.iter().all(query::is_valid) {
+        return Err("Invalid interval");
+    }
+
+    let mut queue = VecDeque::new();
+    match list.sublist(0) {
+        Some((start, end)) if start <= end && end <= n => queue.push_back((start, end, None)),
+        _ => return Err("Invalid top-level list"),
+    }
+
+    let mut visited = vec![false; n];
+    let mut n_visited = 0;
+    while let Some((start, end, parent)) = queue.pop_front() {
+        for i in start..end {
+            if visited[i] {
+                return Err("Interval is part of multiple sublists");
+            }
+            visited[i] = true;
+            n_visited += 1;
+
+            let e = &intervals[i];
+            if let Some(p) = parent.map(|p: usize| &intervals[p]) {
+                if e.start() < p.start() || e.end() > p.end() {
+                    return Err("Interval is not contained in its parent");
+                }
+            }
+            if i > start && (intervals[i - 1].start() > e.start() || intervals[i - 1].end() > e.end()) {
+                return Err("Sublist is not sorted");
+            }
+            if let Some((s, e)) = list.sublist(i + 1) {
+                if s >= e || e > n {
+                    return Err("Invalid sublist");
+                }
+                queue.push_back((s, e, Some(i)));
+            }
+        }
+    }
+
+    if n_visited != n {
+        return Err("Interval is not part of a sublist");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::check;
+    use crate::NClist;
+    use std::ops::Range;
+
+    fn check_parts(intervals: &[Range<u32>], contained: &[Option<(usize, usize)>]) -> Result<(), &'static str> {
+        let nclist = NClist { intervals: intervals.to_vec(), contained: contained.to_vec() };
+        check(&nclist, intervals, contained.len())
+    }
+
+    #[test]
+    fn layout() {
+        let nclist = NClist::from_vec(vec![(10u32..15), (10..20), (1..8), (12..13), (30..40)]).unwrap();
+        assert_eq!(check_parts(&nclist.intervals, &nclist.contained), Ok(()));
+
+        let mut contained = nclist.contained.clone();
+        contained.pop();
+        assert!(check_parts(&nclist.intervals, &contained).is_err());
+
+        //a sublist linked twice
+        let mut contained = nclist.contained.clone();
+        let sublist = contained.iter().skip(1).find_map(|c| *c).unwrap();
+        contained[nclist.len()] = Some(sublist);
+        assert!(check_parts(&nclist.intervals, &contained).is_err());
+
+        let mut intervals = nclist.intervals.clone();
+        intervals.swap(0, 1);
+        assert!(check_parts(&intervals, &nclist.contained).is_err());
+
+        assert_eq!(check_parts(&[], &[Some((0, 0))]), Ok(()));
+    }
+}
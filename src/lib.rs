@@ -19,13 +19,27 @@
 //! O(N) if all intervals are contained within its parent
 //!
 //! The linked article also provides details about an on-disk version that can also be efficiently
-//! searched, but this crate implementation is in-memory and stores the items in a (single) `Vec`.
+//! searched. The `NClist` implementation is in-memory and stores the items in a (single) `Vec`,
+//! but for fixed size interval types this layout can be written to a file and queried without
+//! loading it using a `disk::DiskNClist`. Memory-mapping these files requires the `disk`
+//! feature.
+//!
+//! With the optional `serde` feature an `NClist` can be serialized including its nested layout,
+//! so a deserialized list does not have to be sorted and nested again. For interval types with a fixed
+//! memory layout, `archive::ArchivedNClist` queries an archived list directly from a byte buffer.
 //!
 //! # How to use
 //! You can create a searchable `NClist<T>` from a `Vec<T>` if you implement the
 //! `Interval` trait for `T` The `Interval` trait also requires that `T` is `Ord`. Creating the
 //! NClist validates that the end coordinate is greater than start. This means negative and
-//! zero-width intervals cannot be used in an `NClist<T>`.
+//! zero-width intervals cannot be used in an `NClist<T>`, unless the `Interval` selects closed
+//! intervals or allows empty intervals with its `SEMANTICS`.
+//!
+//! The `bed` and `gff` modules contain readers for BED, GFF3 and GTF files. The records
+//! implement `Interval` and can be used to create an `NClist` directly.
+//!
+//! With the optional `rayon` feature `NClist::par_from_vec` sorts and nests large inputs using
+//! multiple threads, and `par_count_overlaps` and `par_overlaps` answer many queries in parallel.
 //!
 //! # Example
 //! ```
@@ -38,6 +52,8 @@
 //! assert_eq!(nc.count_overlaps(&(10..12)), 2);
 //! // remember, intervals are half open
 //! assert_eq!(nc.count_overlaps(&(20..30)), 0);
+//! // other range types, like `RangeInclusive` or `RangeFrom`, and any `Interval` can be used as query
+//! assert_eq!(nc.count_overlaps(&(15..)), 1);
 //!
 //! //or query them using an iterator
 //! let mut q = nc.overlaps(&(7..10));
@@ -48,29 +64,89 @@
 //!
 //! # Recommendations for use
 //! The `NClist<T>` is not mutable. Any mutable access to the items could invalidate the
-//! interval bounds (interior mutability using for example a `RefCell` could solve this). Also
-//! insertion and deletion are not supported. I can speculate that an interval tree would also be a
-//! better for this type of access. For usage in bioinformatics where interval data is often
-//! provided as (sorted) lists (gff, gtf, bed) the `NClist<T>` is a perfect fit and has very nice
-//! ergonomics.  Obviously the implementation works better when nesting depth is limited, but
-//! performance in simple tests seem consistently better than rust-bio's IntervalTree
+//! interval bounds (interior mutability using for example a `RefCell` could solve this). When
+//! intervals have to be inserted or removed after construction the `DynamicNClist<T>` can be used.
+//! It stores a few `NClist<T>` levels that are merged as they grow. For usage in bioinformatics
+//! where interval data is often provided as (sorted) lists (gff, gtf, bed) the `NClist<T>` is a
+//! perfect fit and has very nice ergonomics.  Obviously the implementation works better when
+//! nesting depth is limited, but performance in simple tests seem consistently better than
+//! rust-bio's IntervalTree
+use std::cmp::Ordering;
 use std::collections::VecDeque;
 use std::convert::TryFrom;
-use std::ops::Range;
+use std::ops::{Bound, Range, RangeInclusive};
 
 use itertools::Itertools;
 
+pub mod archive;
+mod batch;
+pub mod bed;
+mod containment;
+mod coverage;
+pub mod disk;
+pub mod dynamic;
+mod error;
+mod filter;
+mod gaps;
+pub mod genome;
+pub mod gff;
+mod join;
+mod layout;
+mod merge;
+mod nearest;
+mod owned;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod query;
+#[cfg(feature = "serde")]
+mod serialize;
+mod setops;
+pub mod strand;
+
+pub use crate::batch::{CountOverlapsMany, OverlapsMany};
+pub use crate::containment::{ContainedIn, Containing};
+pub use crate::dynamic::DynamicNClist;
+pub use crate::error::Error;
+pub use crate::filter::{Length, MinOverlap, OverlapsMin};
+pub use crate::gaps::Gaps;
+pub use crate::genome::GenomeNClist;
+pub use crate::join::Join;
+pub use crate::merge::{Merge, MergeWithItems, Touches};
+pub use crate::owned::{OwnedOrderedOverlaps, OwnedOverlaps};
+pub use crate::query::Query;
+pub use crate::strand::{Strand, StrandMode, Stranded, StrandedNClist};
+
+/// How the start and end coordinate of an `Interval` are interpreted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Semantics {
+    /// Inclusive start and exclusive end, `end > start` must be true.
+    HalfOpen,
+    /// Inclusive start and end, `end >= start` must be true.
+    Closed,
+    /// Inclusive start and exclusive end, but `end == start` is allowed. An empty interval at `p`
+    /// (an insertion point) is treated as position `p`: it overlaps queries that contain `p`, and
+    /// as query it overlaps the elements that contain `p` (including empty elements at `p`).
+    AllowEmpty,
+}
+
 /// The interval trait needs to be implemented for `T` before you can create an `NClist<T>`.
-/// An interval is half-open, inclusive start and exclusive end (like `std::ops::Range<T>`), but 
-/// `end > start` must always be true.
+/// By default an interval is half-open, inclusive start and exclusive end (like
+/// `std::ops::Range<T>`), and `end > start` must always be true. Other interpretations can be
+/// selected with `SEMANTICS`. The overlap queries follow the semantics of the elements, queries
+/// that compute new coordinates (`merge`, `gaps`, `coverage` and the set operations) treat all
+/// coordinates as half-open.
 pub trait Interval {
     /// The coordinate type of the interval. This type must implement `std::ops::Ord`
     type Coord: Ord;
 
-    /// Return a reference to the start coordinate of the interval (inclusive)
+    /// The interpretation of the coordinates, `Semantics::HalfOpen` by default.
+    const SEMANTICS: Semantics = Semantics::HalfOpen;
+
+    /// Return a reference to the start coordinate of the interval, always inclusive
     fn start(&self) -> &Self::Coord;
 
-    /// Return a reference to the end coordinate of the interval (non-inclusive)
+    /// Return a reference to the end coordinate of the interval, exclusive or inclusive
+    /// depending on `SEMANTICS`
     fn end(&self) -> &Self::Coord;
 }
 
@@ -89,6 +165,22 @@ impl<N> Interval for Range<N> where N: Ord{
     }
 }
 
+/// Interval is implemented for `std::ops::RangeInclusive` with `Semantics::Closed`.
+impl<N> Interval for RangeInclusive<N> where N: Ord {
+    type Coord = N;
+    const SEMANTICS: Semantics = Semantics::Closed;
+
+    #[inline(always)]
+    fn start(&self) -> &Self::Coord {
+        RangeInclusive::start(self)
+    }
+
+    #[inline(always)]
+    fn end(&self) -> &Self::Coord {
+        RangeInclusive::end(self)
+    }
+}
+
 #[derive(Debug)]
 pub struct NClist<T> where T: Interval {
     intervals: Vec<T>,
@@ -98,22 +190,43 @@ pub struct NClist<T> where T: Interval {
 struct SlicedNClist<'a, T> where T: 'a + Interval {
     intervals: &'a [T],
     contained: &'a [Option<(usize, usize)>],
-    stop_at: &'a T::Coord
+    stop_at: Bound<&'a T::Coord>,
 }
 
 pub struct Overlaps<'a, T> where T: 'a + Interval {
     nclist: &'a NClist<T>,
-    range:  &'a Range<T::Coord>,
+    start: Option<&'a T::Coord>,
+    end: Bound<&'a T::Coord>,
+    state: OverlapsState,
+}
+
+pub struct OrderedOverlaps<'a, T> where T: 'a + Interval {
+    nclist: &'a NClist<T>,
+    start: Option<&'a T::Coord>,
+    end: Bound<&'a T::Coord>,
+    state: OrderedState,
+}
+
+/// The position of an overlap query, without a reference to the query bounds.
+pub(crate) struct OverlapsState {
     current_pos: usize,
     current_end: usize,
     sublists: VecDeque<(usize, usize)>,
 }
 
-pub struct OrderedOverlaps<'a, T> where T: 'a + Interval {
+/// The position of an ordered overlap query, without a reference to the query bounds. The
+/// sublists on the stack continue after the current sublist has been exhausted.
+pub(crate) struct OrderedState {
+    current_pos: usize,
+    current_end: usize,
+    stack: Vec<(usize, usize)>,
+}
+
+pub struct Iter<'a, T> where T: 'a + Interval {
     nclist: &'a NClist<T>,
-    range:  &'a Range<T::Coord>,
-    current: SlicedNClist<'a, T>,
-    queue: Vec<SlicedNClist<'a, T>>
+    current_pos: usize,
+    current_end: usize,
+    stack: Vec<(usize, usize)>,
 }
 
 impl<T> NClist<T> where T: Interval {
@@ -121,34 +234,144 @@ impl<T> NClist<T> where T: Interval {
         NClist { intervals: Vec::new(), contained: vec![Some((0,0))] }
     }
 
-    pub fn from_vec(mut v: Vec<T>) -> Result<NClist<T>, &'static str> {
-        if v.iter().any(|e| e.end() <= e.start()) {
-            return Err("Cannot use intervals with zero or negative width");
+    /// Create an `NClist` from `v`. This fails when an interval is not valid for its
+    /// `Semantics`, the error contains the position of the first invalid interval and returns
+    /// `v`.
+    pub fn from_vec(v: Vec<T>) -> Result<NClist<T>, Error<T>> {
+        Error::check(v, |e| e).map(NClist::build)
+    }
+
+    /// Create an `NClist` from `v`, which must be sorted by ascending start coordinate and
+    /// descending end coordinate (the order of `iter`). The intervals are not sorted again. This
+    /// fails when an interval is not valid or is out of order, the error contains the position
+    /// of the first offending interval and returns `v`.
+    pub fn from_sorted_vec(v: Vec<T>) -> Result<NClist<T>, Error<T>> {
+        let v = Error::check(v, |e| e)?;
+        match v.windows(2).position(|w| sort_order(&w[0], &w[1]) == Ordering::Greater) {
+            Some(i) => Err(Error::Unsorted { index: i + 1, intervals: v }),
+            None => Ok(NClist::nest(v)),
         }
-        v.sort_by(|a, b| a.start().cmp(b.start())
-                  .then(a.end().cmp(b.end()).reverse()));
+    }
 
+    /// Create an `NClist` from the sorted intervals in `it` like `from_sorted_vec`, checking
+    /// every interval as it is read. The intervals are nested while they are read, without
+    /// collecting them first. On failure the error contains the intervals read so far, ending
+    /// with the offending interval, and the remainder of `it` is not consumed.
+    pub fn from_sorted_iter<I>(it: I) -> Result<NClist<T>, Error<T>> where I: IntoIterator<Item = T> {
+        let mut list = NClist { intervals: Vec::new(), contained: vec![None] };
+        //the open sublists, every sublist is contained in the last interval of the one before it
+        let mut open: Vec<OpenSublist<T>> = vec![Vec::new()];
+        for e in it {
+            let valid = query::is_valid(&e);
+            let sorted = match open.last().and_then(|sublist| sublist.last()) {
+                Some((prev, _)) => sort_order(prev, &e) != Ordering::Greater,
+                None => true,
+            };
+            if !valid || !sorted {
+                //restore the order of the intervals read so far
+                let mut intervals = list.intervals;
+                intervals.extend(open.into_iter().flatten().map(|(e, _)| e));
+                intervals.sort_by(sort_order);
+                let index = intervals.len();
+                intervals.push(e);
+                return Err(if valid {
+                    Error::Unsorted { index, intervals }
+                } else {
+                    Error::InvalidInterval { index, intervals }
+                });
+            }
+
+            while open.len() > 1 && open[open.len() - 2].last().unwrap().0.end() <= e.end() {
+                list.close_sublist(&mut open);
+            }
+            let innermost = open.last_mut().unwrap();
+            if matches!(innermost.last(), Some((parent, _)) if e.end() < parent.end()) {
+                open.push(vec![(e, None)]);
+            } else {
+                innermost.push((e, None));
+            }
+        }
+
+        while open.len() > 1 {
+            list.close_sublist(&mut open);
+        }
+        list.contained[0] = Some(list.push_sublist(open.pop().unwrap()));
+        Ok(list)
+    }
+
+    /// Create an `NClist` from `v` without checking the interval width and order. If `v` is not
+    /// sorted like `from_sorted_vec` requires, or contains invalid intervals, queries return
+    /// incorrect results.
+    pub fn from_sorted_vec_unchecked(v: Vec<T>) -> NClist<T> {
+        NClist::nest(v)
+    }
+
+    /// Store the innermost sublist of `open` and link it from its parent.
+    fn close_sublist(&mut self, open: &mut Vec<OpenSublist<T>>) {
+        let sublist = self.push_sublist(open.pop().unwrap());
+        open.last_mut().unwrap().last_mut().unwrap().1 = Some(sublist);
+    }
+
+    /// Append the intervals of a complete sublist and return its position.
+    fn push_sublist(&mut self, sublist: OpenSublist<T>) -> (usize, usize) {
+        let start = self.intervals.len();
+        for (e, contained) in sublist {
+            self.intervals.push(e);
+            self.contained.push(contained);
+        }
+        (start, self.intervals.len())
+    }
+
+    /// Sort and nest the intervals in `v`. The interval width must have been validated.
+    pub(crate) fn build(mut v: Vec<T>) -> NClist<T> {
+        v.sort_by(sort_order);
+        NClist::nest(v)
+    }
+
+    /// Nest the intervals in `v`, which must be sorted in `sort_order`.
+    pub(crate) fn nest(v: Vec<T>) -> NClist<T> {
         let mut list = NClist::new();
         let mut sublists = VecDeque::from(vec![NClistBuilder { intervals: v, contained_pos: 0}]);
 
         while !sublists.is_empty() {
             build_nclist(&mut sublists, &mut list);
         }
-        Ok(list)
+        list
     }
 
-    /// Count the number of elements overlapping the `Range` r. Counting overlaps is slightly
-    /// faster than iterating over the overlaps. This method is preferred when only the number of
-    /// overlapping elements is required.
-    pub fn count_overlaps(&self, r: &Range<T::Coord>) -> usize {
-        if r.end <= r.start {
-            return 0;
-        }
+    /// Returns the number of intervals in the `NClist`.
+    pub fn len(&self) -> usize {
+        self.intervals.len()
+    }
+
+    /// Returns `true` if the `NClist` contains no intervals.
+    pub fn is_empty(&self) -> bool {
+        self.intervals.is_empty()
+    }
+
+    /// Count the number of elements overlapping query `q`, a `Range` or any other `Query` type.
+    /// Counting overlaps is slightly faster than iterating over the overlaps. This method is
+    /// preferred when only the number of overlapping elements is required.
+    pub fn count_overlaps<Q>(&self, q: &Q) -> usize where Q: Query<T::Coord> + ?Sized {
+        self.count(q.lower(), q.upper())
+    }
+
+    /// Count the number of elements that contain coordinate `p` according to their `SEMANTICS`,
+    /// e.g. `start <= p < end` for half-open elements.
+    pub fn count_stab(&self, p: &T::Coord) -> usize {
+        self.count(Some(p), Bound::Included(p))
+    }
+
+    /// Count the elements ending after `q` and starting before `q_end`.
+    fn count(&self, q: Option<&T::Coord>, q_end: Bound<&T::Coord>) -> usize {
         let mut count = 0;
+        if query::is_empty(q, q_end) {
+            return count;
+        }
         let mut queue = VecDeque::new();
         queue.push_back(self.contained[0].unwrap());
         while let Some((start, end)) = queue.pop_front() {
-            self.slice(start, end, &r.start, &r.end)
+            self.slice(start, end, q, q_end)
                 .for_each(|(_, contained)| {
                     count += 1;
                     if let Some(subrange) = *contained {
@@ -159,30 +382,40 @@ impl<T> NClist<T> where T: Interval {
         count
     }
 
-    /// Returns an iterator that returns overlapping elements to query `r`. During iteration
-    /// contained intervals are pushed to a queue an processed in order after yielding the
-    /// non-overlapping regions.
-    pub fn overlaps<'a>(&'a self, r: &'a Range<T::Coord>) -> Overlaps<'a , T> {
-        let current_slice = self.contained[0].as_ref().unwrap();
-        //empty or negative width intervals do not overlap anything
-        let start = if r.end > r.start {
-            self.bin_search_end(current_slice.0, current_slice.1, &r.start)
-        } else {
-            current_slice.1
-        };
+    /// Returns an iterator that returns overlapping elements to query `q`, a `Range` or any
+    /// other `Query` type. During iteration contained intervals are pushed to a queue an processed
+    /// in order after yielding the non-overlapping regions.
+    pub fn overlaps<'a, Q>(&'a self, q: &'a Q) -> Overlaps<'a , T> where Q: Query<T::Coord> + ?Sized {
+        self.overlaps_between(q.lower(), q.upper())
+    }
 
-        Overlaps { nclist: self, range: r, current_pos: start, current_end: current_slice.1, sublists: VecDeque::new() }
+    /// Returns an iterator that returns the elements that contain coordinate `p` according to their
+    /// `SEMANTICS`, e.g. `start <= p < end` for half-open elements. This is a query for a single
+    /// position that does not require creating a `Range`.
+    pub fn stab<'a>(&'a self, p: &'a T::Coord) -> Overlaps<'a, T> {
+        self.overlaps_between(Some(p), Bound::Included(p))
     }
 
-    /// Returns an iterator that returns overlapping elements to query `r` ordered by start
+    pub(crate) fn overlaps_between<'a>(&'a self, q: Option<&'a T::Coord>, q_end: Bound<&'a T::Coord>) -> Overlaps<'a, T> {
+        Overlaps { nclist: self, start: q, end: q_end, state: OverlapsState::new(self, q, q_end) }
+    }
+
+    /// Returns an iterator that returns overlapping elements to query `q` ordered by start
     /// coordinate. This is less efficient that returning without ordering, but doesn't require
     /// allocating storage for all overlapping elements.
-    pub fn overlaps_ordered<'a>(&'a self, r: &'a Range<T::Coord>) -> OrderedOverlaps<'a , T> {
-        let &(mut start, end) = self.contained[0].as_ref().unwrap();
-        if r.end <= r.start {
-            start = end;
-        }
-        OrderedOverlaps { nclist: self, range: r, current: self.slice(start, end, &r.start, &r.end), queue: Vec::new() }
+    pub fn overlaps_ordered<'a, Q>(&'a self, q: &'a Q) -> OrderedOverlaps<'a , T> where Q: Query<T::Coord> + ?Sized {
+        self.overlaps_ordered_between(q.lower(), q.upper())
+    }
+
+    pub(crate) fn overlaps_ordered_between<'a>(&'a self, q: Option<&'a T::Coord>, q_end: Bound<&'a T::Coord>) -> OrderedOverlaps<'a, T> {
+        OrderedOverlaps { nclist: self, start: q, end: q_end, state: OrderedState::new(self, q, q_end) }
+    }
+
+    /// Returns an iterator over all elements ordered by start coordinate (and descending end
+    /// coordinate for elements with the same start).
+    pub fn iter(&self) -> Iter<'_, T> {
+        let &(start, end) = self.contained[0].as_ref().unwrap();
+        Iter { nclist: self, current_pos: start, current_end: end, stack: Vec::new() }
     }
 
     /// Return the intervals `Vec`. This will run without allocation and return the intervals in a
@@ -192,21 +425,60 @@ impl<T> NClist<T> where T: Interval {
     }
 
     #[inline]
-    fn slice<'a>(&'a self, mut start: usize, end: usize, q:  &T::Coord, q_end: &'a T::Coord) -> SlicedNClist<'a, T> {
-        start += match self.intervals[start..end].binary_search_by(|e| e.end().cmp(q))
-        {
-            Ok(n) => n + 1,
-            Err(n) => n
-        };
+    fn slice<'a>(&'a self, mut start: usize, end: usize, q: Option<&T::Coord>, q_end: Bound<&'a T::Coord>) -> SlicedNClist<'a, T> {
+        start += self.skip_ending_before(start, end, q);
         SlicedNClist { intervals: &self.intervals[start..end], contained: &self.contained[start+1..end+1], stop_at: q_end }
     }
 
     #[inline]
     fn bin_search_end(&self, start: usize, end: usize, q: &T::Coord) -> usize {
-        match self.intervals[start..end].binary_search_by(|e| e.end().cmp(q)) {
+        count_ending_before(&self.intervals[start..end], q)
+    }
+}
+
+/// The number of elements in sublist `list` that end before query start `q`.
+#[inline]
+fn count_ending_before<T: Interval>(list: &[T], q: &T::Coord) -> usize {
+    match T::SEMANTICS {
+        Semantics::HalfOpen => match list.binary_search_by(|e| e.end().cmp(q)) {
             Ok(n) => n + 1,
             Err(n) => n
-        }
+        },
+        _ => list.partition_point(|e| query::ends_before(e, q)),
+    }
+}
+
+/// The flattened `intervals` and `contained` storage of an `NClist`, either in memory, in an
+/// `archive::ArchivedNClist` or in a `disk::DiskNClist`. The query state is shared by all of them.
+pub(crate) trait Layout {
+    type Item: Interval;
+
+    /// Returns `true` if interval `i` starts before the query end `q_end`.
+    fn starts_before(&self, i: usize, q_end: Bound<&<Self::Item as Interval>::Coord>) -> bool;
+
+    /// The sublist contained in interval `i - 1`, or the top-level list for `i == 0`.
+    fn sublist(&self, i: usize) -> Option<(usize, usize)>;
+
+    /// The number of elements in `start..end` that end before query start `q`.
+    fn skip_ending_before(&self, start: usize, end: usize, q: Option<&<Self::Item as Interval>::Coord>) -> usize;
+}
+
+impl<T> Layout for NClist<T> where T: Interval {
+    type Item = T;
+
+    #[inline(always)]
+    fn starts_before(&self, i: usize, q_end: Bound<&T::Coord>) -> bool {
+        query::starts_before(self.intervals[i].start(), q_end)
+    }
+
+    #[inline(always)]
+    fn sublist(&self, i: usize) -> Option<(usize, usize)> {
+        self.contained[i]
+    }
+
+    #[inline]
+    fn skip_ending_before(&self, start: usize, end: usize, q: Option<&T::Coord>) -> usize {
+        q.map_or(0, |q| count_ending_before(&self.intervals[start..end], q))
     }
 }
 
@@ -214,13 +486,13 @@ impl<'a, T> Iterator for SlicedNClist<'a, T> where T: Interval {
     type Item = (&'a T, &'a Option<(usize, usize)>);
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some((i, ref mut intervals)) = self.intervals.split_first() {
-            if i.start() >= self.stop_at {
+        if let Some((i, intervals)) = self.intervals.split_first() {
+            if !query::starts_before(i.start(), self.stop_at) {
                 None
             } else {
-                let (c, ref mut contained) = self.contained.split_first().unwrap();
-                std::mem::replace(&mut self.intervals, intervals);
-                std::mem::replace(&mut self.contained, contained);
+                let (c, contained) = self.contained.split_first().unwrap();
+                self.intervals = intervals;
+                self.contained = contained;
                 Some((i, c))
             }
         } else {
@@ -229,45 +501,125 @@ impl<'a, T> Iterator for SlicedNClist<'a, T> where T: Interval {
     }
 }
 
+impl OverlapsState {
+    pub(crate) fn new<L: Layout>(nclist: &L, q: Option<&<L::Item as Interval>::Coord>, q_end: Bound<&<L::Item as Interval>::Coord>) -> OverlapsState {
+        let (start, end) = nclist.sublist(0).unwrap();
+        //empty queries do not overlap anything
+        let current_pos = if query::is_empty(q, q_end) {
+            end
+        } else {
+            start + nclist.skip_ending_before(start, end, q)
+        };
+        OverlapsState { current_pos, current_end: end, sublists: VecDeque::new() }
+    }
+
+    /// Advance to the next element overlapping `q` to `q_end` and return its position in the
+    /// `intervals` vector.
+    #[inline]
+    pub(crate) fn next_index<L: Layout>(&mut self, nclist: &L, q: Option<&<L::Item as Interval>::Coord>, q_end: Bound<&<L::Item as Interval>::Coord>) -> Option<usize> {
+        loop {
+            if self.current_pos < self.current_end && nclist.starts_before(self.current_pos, q_end) {
+                let pos = self.current_pos;
+                self.current_pos += 1;
+                if let Some(next_sublist) = nclist.sublist(self.current_pos) {
+                    self.sublists.push_back(next_sublist);
+                }
+                return Some(pos);
+            }
+            let (start, end) = self.sublists.pop_front()?;
+            self.current_pos = start + nclist.skip_ending_before(start, end, q);
+            self.current_end = end;
+        }
+    }
+}
+
+impl<'a, T> Overlaps<'a, T> where T: Interval {
+    /// Advance the iterator and return the position of the next overlapping element in the
+    /// `intervals` vector.
+    #[inline]
+    pub(crate) fn next_index(&mut self) -> Option<usize> {
+        self.state.next_index(self.nclist, self.start, self.end)
+    }
+}
+
 impl<'a, T> Iterator for Overlaps<'a, T> where T: Interval {
     type Item = &'a T;
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        let remaining = self.current_end - self.current_pos;
-
-        if remaining == 0 || *self.nclist.intervals[self.current_pos].start() >= self.range.end {
-            if let Some((mut new_start, new_end)) = self.sublists.pop_front() {
-                new_start += self.nclist.bin_search_end(new_start, new_end, &self.range.start);
-                self.current_pos = new_start;
-                self.current_end = new_end;
-                self.next()
-            } else {
-                None
-            }
+        let nclist = self.nclist;
+        self.next_index().map(|i| &nclist.intervals[i])
+    }
+}
+
+impl OrderedState {
+    pub(crate) fn new<L: Layout>(nclist: &L, q: Option<&<L::Item as Interval>::Coord>, q_end: Bound<&<L::Item as Interval>::Coord>) -> OrderedState {
+        let (start, end) = nclist.sublist(0).unwrap();
+        let current_pos = if query::is_empty(q, q_end) {
+            end
         } else {
-            let pos = self.current_pos;
-            self.current_pos += 1;
-            if let Some(next_sublist) = self.nclist.contained[self.current_pos] {
-                self.sublists.push_back(next_sublist);
+            start + nclist.skip_ending_before(start, end, q)
+        };
+        OrderedState { current_pos, current_end: end, stack: Vec::new() }
+    }
+
+    /// Advance to the next element overlapping `q` to `q_end` in sorted order and return its
+    /// position in the `intervals` vector.
+    #[inline]
+    pub(crate) fn next_index<L: Layout>(&mut self, nclist: &L, q: Option<&<L::Item as Interval>::Coord>, q_end: Bound<&<L::Item as Interval>::Coord>) -> Option<usize> {
+        loop {
+            if self.current_pos < self.current_end && nclist.starts_before(self.current_pos, q_end) {
+                let pos = self.current_pos;
+                self.current_pos += 1;
+                //contained intervals sort before the next interval in this sublist
+                if let Some((start, end)) = nclist.sublist(self.current_pos) {
+                    self.stack.push((self.current_pos, self.current_end));
+                    self.current_pos = start + nclist.skip_ending_before(start, end, q);
+                    self.current_end = end;
+                }
+                return Some(pos);
             }
-            Some(&self.nclist.intervals[pos])
+            let (pos, end) = self.stack.pop()?;
+            self.current_pos = pos;
+            self.current_end = end;
         }
     }
 }
 
+impl<'a, T> OrderedOverlaps<'a, T> where T: Interval {
+    /// Advance the iterator and return the position of the next overlapping element in the
+    /// `intervals` vector.
+    #[inline]
+    pub(crate) fn next_index(&mut self) -> Option<usize> {
+        self.state.next_index(self.nclist, self.start, self.end)
+    }
+}
+
 impl<'a, T> Iterator for OrderedOverlaps<'a, T> where T: Interval {
     type Item = &'a T;
     #[inline]
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some((interval, contained)) = self.current.next() {
-            if let Some((start, end)) = *contained {
-                let mut ns = self.nclist.slice(start, end, &self.range.start, &self.range.end);
-                std::mem::swap(&mut self.current, &mut ns);
-                self.queue.push(ns);
+        let nclist = self.nclist;
+        self.next_index().map(|i| &nclist.intervals[i])
+    }
+}
+
+impl<'a, T> Iterator for Iter<'a, T> where T: Interval {
+    type Item = &'a T;
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.current_pos < self.current_end {
+            let pos = self.current_pos;
+            self.current_pos += 1;
+            //contained intervals sort before the next interval in this sublist
+            if let Some((start, end)) = self.nclist.contained[self.current_pos] {
+                self.stack.push((self.current_pos, self.current_end));
+                self.current_pos = start;
+                self.current_end = end;
             }
-            Some(interval)
-        } else if let Some(sublist) = self.queue.pop() {
-            self.current = sublist;
+            Some(&self.nclist.intervals[pos])
+        } else if let Some((pos, end)) = self.stack.pop() {
+            self.current_pos = pos;
+            self.current_end = end;
             self.next()
         } else {
             None
@@ -275,6 +627,24 @@ impl<'a, T> Iterator for OrderedOverlaps<'a, T> where T: Interval {
     }
 }
 
+impl<'a, T> IntoIterator for &'a NClist<T> where T: Interval {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// The order of the intervals in a sublist: ascending start and descending end coordinate.
+#[inline]
+fn sort_order<T: Interval>(a: &T, b: &T) -> Ordering {
+    a.start().cmp(b.start()).then(a.end().cmp(b.end()).reverse())
+}
+
+/// Internal sublist that is still read by `NClist::from_sorted_iter`, with the position of the
+/// sublist of every interval
+type OpenSublist<T> = Vec<(T, Option<(usize, usize)>)>;
+
 /// Internal intermediate sublist used for creating `NClist<T>`
 struct NClistBuilder<T> {
     intervals: Vec<T>,
@@ -303,7 +673,7 @@ fn build_nclist<T: Interval>(sublists: &mut VecDeque<NClistBuilder<T>>, result:
 }
 
 impl<T> TryFrom<Vec<T>> for NClist<T> where T: Interval {
-    type Error = &'static str;
+    type Error = Error<T>;
     fn try_from(v: Vec<T>) -> Result<Self, Self::Error> {
         NClist::from_vec(v)
     }
@@ -311,9 +681,9 @@ impl<T> TryFrom<Vec<T>> for NClist<T> where T: Interval {
 
 /// Return the intervals `Vec`. This will run without allocation and return the intervals in a
 /// different order then provided.
-impl<T> Into<Vec<T>> for NClist<T> where T: Interval {
-    fn into(self) -> Vec<T> {
-        self.intervals
+impl<T> From<NClist<T>> for Vec<T> where T: Interval {
+    fn from(nclist: NClist<T>) -> Vec<T> {
+        nclist.intervals
     }
 }
 
@@ -321,6 +691,34 @@ impl<T> Into<Vec<T>> for NClist<T> where T: Interval {
 mod tests {
     use super::*;
 
+    /// Returns `n` intervals starting in `0..space` with a width in `1..=max_width`. The
+    /// intervals are random, but the same for every call with the same arguments.
+    pub(crate) fn random_intervals(n: usize, space: u32, max_width: u32) -> Vec<Range<u32>> {
+        use rand::{Rng, SeedableRng};
+
+        let seed = (n as u64) << 32 ^ u64::from(space) << 16 ^ u64::from(max_width);
+        let mut rng = rand::rngs::StdRng::seed_from_u64(seed);
+        (0..n).map(|_| {
+            let start = rng.gen_range(0, space);
+            start..start + rng.gen_range(1, max_width + 1)
+        }).collect()
+    }
+
+    /// An interval type that allows empty intervals, used in the tests of all modules.
+    #[derive(Debug, Clone, PartialEq)]
+    pub(crate) struct Site(pub(crate) Range<u32>);
+
+    impl Interval for Site {
+        type Coord = u32;
+        const SEMANTICS: Semantics = Semantics::AllowEmpty;
+        fn start(&self) -> &u32 {
+            &self.0.start
+        }
+        fn end(&self) -> &u32 {
+            &self.0.end
+        }
+    }
+
     // This test is copied from Rust's stdlib. This software relies on the fact that a binary
     // search returns the last matching element. If the stdlib implementation changes this should
     // be caught.
@@ -358,14 +756,20 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::reversed_empty_ranges)]
     fn interval_width() {
         let list: Vec<Range<u64>> = vec![(5..20), (19..20), (7..7)].into_iter().collect();
         assert!(NClist::from_vec(list).is_err());
         let list: Vec<Range<u64>> = vec![(5..20), (20..19), (7..8)].into_iter().collect();
-        assert!(NClist::from_vec(list).is_err());
+        let err = NClist::from_vec(list).unwrap_err();
+        assert_eq!(err.index(), 1);
+        assert_eq!(err.interval(), &(20..19));
+        assert_eq!(err.to_string(), "Invalid interval at index 1: 20..19");
+        assert_eq!(err.into_vec(), vec![(5..20), (20..19), (7..8)]);
     }
 
     #[test]
+    #[allow(clippy::reversed_empty_ranges)]
     fn illegal_width_queries() {
         let list: Vec<Range<u64>> = vec![(5..20), (19..20), (7..8)].into_iter().collect();
         let nclist = NClist::from_vec(list).unwrap();
@@ -380,6 +784,84 @@ mod tests {
         assert_eq!(nclist.overlaps_ordered(&(19..19)).count(), 0);
     }
 
+    #[test]
+    fn closed_intervals() {
+        let list = vec![(10..=15), (15..=15), (1..=8), (16..=20)];
+        let nclist = NClist::from_vec(list).unwrap();
+        assert_eq!(nclist.count_overlaps(&(15..16)), 2);
+        assert_eq!(nclist.count_overlaps(&(8..10)), 1);
+        assert_eq!(nclist.count_overlaps(&(9..10)), 0);
+        assert_eq!(nclist.count_overlaps(&(8..=10)), 2);
+        assert_eq!(nclist.count_stab(&15), 2);
+        assert_eq!(nclist.count_stab(&20), 1);
+        assert_eq!(nclist.overlaps_ordered(&(15..=16)).cloned().collect::<Vec<_>>(), vec![10..=15, 15..=15, 16..=20]);
+        #[allow(clippy::reversed_empty_ranges)]
+        let negative = vec![(5..=4)];
+        assert!(NClist::from_vec(negative).is_err());
+    }
+
+    #[test]
+    fn empty_intervals() {
+        let list = vec![Site(10..20), Site(10..10), Site(15..15), Site(20..20), Site(5..10)];
+        let nclist = NClist::from_vec(list).unwrap();
+        assert_eq!(nclist.len(), 5);
+        assert_eq!(nclist.count_overlaps(&(15..16)), 2);
+        assert_eq!(nclist.count_overlaps(&(10..11)), 2);
+        assert_eq!(nclist.count_overlaps(&(9..10)), 1);
+        assert_eq!(nclist.count_overlaps(&(20..21)), 1);
+        assert_eq!(nclist.count_stab(&10), 2);
+        //an empty query is a single position
+        assert_eq!(nclist.count_overlaps(&Site(15..15)), 2);
+        assert_eq!(nclist.count_overlaps(&Site(20..20)), 1);
+        let found: Vec<_> = nclist.overlaps_ordered(&(0..30)).map(|s| s.0.clone()).collect();
+        assert_eq!(found, vec![5..10, 10..20, 10..10, 15..15, 20..20]);
+
+        assert!(NClist::from_vec(vec![Site(10..10)]).is_ok());
+    }
+
+    #[test]
+    fn from_sorted() {
+        let list: Vec<Range<u64>> = vec![(1..8), (10..20), (10..15), (12..13), (30..40)];
+        let nclist = NClist::from_sorted_vec(list.clone()).unwrap();
+        assert!(nclist.iter().eq(list.iter()));
+        assert_eq!(nclist.count_overlaps(&(12..14)), 3);
+        let nclist = NClist::from_sorted_iter(list.clone()).unwrap();
+        assert!(nclist.iter().eq(list.iter()));
+        assert!(NClist::from_sorted_vec_unchecked(list).iter().eq(nclist.iter()));
+
+        let unsorted: Vec<Range<u64>> = vec![(1..8), (10..15), (10..20), (30..40)];
+        let err = NClist::from_sorted_vec(unsorted.clone()).unwrap_err();
+        assert_eq!(err.index(), 2);
+        assert_eq!(err.to_string(), "Interval at index 2 is out of order: 10..20");
+        assert_eq!(err.into_vec(), unsorted);
+
+        let mut it = unsorted.into_iter();
+        let err = NClist::from_sorted_iter(&mut it).unwrap_err();
+        assert!(matches!(err, Error::Unsorted { index: 2, .. }));
+        assert_eq!(err.into_vec(), vec![(1..8), (10..15), (10..20)]);
+        assert_eq!(it.next(), Some(30..40));
+
+        let invalid: Vec<Range<u64>> = vec![(1..8), (9..9)];
+        assert!(matches!(NClist::from_sorted_iter(invalid.clone()), Err(Error::InvalidInterval { index: 1, .. })));
+        assert!(matches!(NClist::from_sorted_vec(invalid), Err(Error::InvalidInterval { index: 1, .. })));
+
+        let nested: Vec<Range<u64>> = vec![(1..50), (2..10), (3..5), (3..4), (6..9), (12..20), (13..14), (30..40), (60..70), (60..70)];
+        let nclist = NClist::from_sorted_iter(nested.clone()).unwrap();
+        assert_eq!(layout::check(&nclist, &nclist.intervals, nclist.contained.len()), Ok(()));
+        assert!(nclist.iter().eq(nested.iter()));
+        let expected = NClist::from_sorted_vec(nested.clone()).unwrap();
+        for q in (0..75).map(|s| s..s + 1 + s % 5) {
+            assert_eq!(nclist.count_overlaps(&q), expected.count_overlaps(&q));
+            assert!(nclist.overlaps_ordered(&q).eq(expected.overlaps_ordered(&q)));
+        }
+
+        let mut unsorted = nested;
+        unsorted.push(5..6);
+        let err = NClist::from_sorted_iter(unsorted.clone()).unwrap_err();
+        assert!(matches!(err, Error::Unsorted { index: 10, .. }));
+        assert_eq!(err.into_vec(), unsorted);
+    }
+
     #[test]
     fn count() {
         let list: Vec<Range<u64>> = vec![(10..15), (10..20), (1..8)].into_iter().collect();
@@ -441,4 +923,38 @@ mod tests {
         assert_eq!(nclist.overlaps(&(8..10)).count(), 0);
         assert_eq!(nclist.overlaps(&(8..9)).count(), 0);
     }
+
+    #[test]
+    fn stab() {
+        let list: Vec<Range<u64>> = vec![(10..15), (10..20), (1..8), (12..13)];
+        let nclist = NClist::from_vec(list).unwrap();
+
+        assert_eq!(nclist.count_stab(&10), 2);
+        assert_eq!(nclist.count_stab(&12), 3);
+        assert_eq!(nclist.count_stab(&15), 1);
+        assert_eq!(nclist.count_stab(&8), 0);
+        assert_eq!(nclist.count_stab(&20), 0);
+
+        let mut q = nclist.stab(&7);
+        assert_eq!(q.next(), Some(&(1..8)));
+        assert_eq!(q.next(), None);
+        assert_eq!(nclist.stab(&12).count(), 3);
+        assert_eq!(nclist.stab(&0).count(), 0);
+        for p in 0..25 {
+            assert_eq!(nclist.stab(&p).count(), nclist.count_overlaps(&(p..p + 1)));
+        }
+    }
+
+    #[test]
+    fn iter() {
+        let list: Vec<Range<u64>> = vec![(10..15), (10..20), (1..8), (0..10), (2..12), (5..6), (11..12)];
+        let mut sorted = list.clone();
+        sorted.sort_by(|a, b| a.start.cmp(&b.start).then(b.end.cmp(&a.end)));
+        let nclist = NClist::from_vec(list).unwrap();
+        assert!(nclist.iter().eq(sorted.iter()));
+        assert_eq!((&nclist).into_iter().count(), 7);
+
+        let nclist: NClist<Range<u64>> = NClist::from_vec(Vec::new()).unwrap();
+        assert_eq!(nclist.iter().next(), None);
+    }
 }
@@ -0,0 +1,183 @@
+//! Merging overlapping intervals into non-overlapping blocks.
+//!
+//! The top-level list of an `NClist` is sorted on start and end coordinate and contains every
+//! interval that is not contained in another interval. Contained intervals cannot extend a block,
+//! so merging only has to walk the top-level list.
+use std::ops::{Range, Sub};
+
+use crate::{Interval, NClist};
+
+/// The default test if an interval starting at the second coordinate joins a block ending at the
+/// first coordinate.
+pub type Touches<C> = fn(&C, &C) -> bool;
+
+/// Iterator over the merged blocks of an `NClist`, created by `NClist::merge` or
+/// `NClist::merge_with_gap`.
+pub struct Merge<'a, T, F = Touches<<T as Interval>::Coord>> where T: 'a + Interval {
+    nclist: &'a NClist<T>,
+    pos: usize,
+    end: usize,
+    joins: F,
+}
+
+/// Iterator over the merged blocks of an `NClist` and the intervals in every block, created by
+/// `Merge::with_items`.
+pub struct MergeWithItems<'a, T, F = Touches<<T as Interval>::Coord>> where T: 'a + Interval {
+    merge: Merge<'a, T, F>,
+}
+
+impl<T> NClist<T> where T: Interval {
+    /// Returns an iterator over the minimal set of non-overlapping ranges that cover all
+    /// elements. Elements that overlap or touch (book-ended elements) are merged into one range.
+    /// The ranges are ordered by start coordinate.
+    pub fn merge(&self) -> Merge<'_, T> {
+        fn touches<C: Ord>(block_end: &C, start: &C) -> bool {
+            start <= block_end
+        }
+        let &(pos, end) = self.contained[0].as_ref().unwrap();
+        Merge { nclist: self, pos, end, joins: touches }
+    }
+
+    /// Returns an iterator over merged ranges like `merge`, but also merges elements that are
+    /// separated by a gap of at most `max_gap`.
+    pub fn merge_with_gap(&self, max_gap: T::Coord) -> Merge<'_, T, impl Fn(&T::Coord, &T::Coord) -> bool>
+        where T::Coord: Clone + Sub<Output = T::Coord>
+    {
+        let &(pos, end) = self.contained[0].as_ref().unwrap();
+        let joins = move |block_end: &T::Coord, start: &T::Coord| {
+            start <= block_end || start.clone() - block_end.clone() <= max_gap
+        };
+        Merge { nclist: self, pos, end, joins }
+    }
+}
+
+impl<'a, T, F> Merge<'a, T, F> where T: Interval, F: Fn(&T::Coord, &T::Coord) -> bool {
+    /// Also return the elements contributing to every merged range.
+    pub fn with_items(self) -> MergeWithItems<'a, T, F> {
+        MergeWithItems { merge: self }
+    }
+
+    /// The positions of the top-level elements in the next block, and the position of the last
+    /// non-empty element that ends the block. Empty elements do not cover any position and never
+    /// start or extend a block.
+    fn next_block(&mut self) -> Option<(Range<usize>, usize)> {
+        let intervals = &self.nclist.intervals;
+        while self.pos < self.end && is_empty(&intervals[self.pos]) {
+            self.pos += 1;
+        }
+        if self.pos == self.end {
+            return None;
+        }
+        let first = self.pos;
+        let mut last = first;
+        self.pos += 1;
+        //top-level elements are sorted on end coordinate, the last non-empty one ends the block
+        while self.pos < self.end && (self.joins)(intervals[last].end(), intervals[self.pos].start()) {
+            if !is_empty(&intervals[self.pos]) {
+                last = self.pos;
+            }
+            self.pos += 1;
+        }
+        //empty elements after the end of the block are skipped by the next call
+        while self.pos > last + 1 && intervals[self.pos - 1].start() > intervals[last].end() {
+            self.pos -= 1;
+        }
+        Some((first..self.pos, last))
+    }
+}
+
+impl<'a, T, F> Iterator for Merge<'a, T, F>
+    where T: Interval, T::Coord: Clone, F: Fn(&T::Coord, &T::Coord) -> bool
+{
+    type Item = Range<T::Coord>;
+    fn next(&mut self) -> Option<Self::Item> {
+        let (block, last) = self.next_block()?;
+        let intervals = &self.nclist.intervals;
+        Some(intervals[block.start].start().clone()..intervals[last].end().clone())
+    }
+}
+
+impl<'a, T, F> Iterator for MergeWithItems<'a, T, F>
+    where T: Interval, T::Coord: Clone, F: Fn(&T::Coord, &T::Coord) -> bool
+{
+    type Item = (Range<T::Coord>, Vec<&'a T>);
+    fn next(&mut self) -> Option<Self::Item> {
+        let (block, last) = self.merge.next_block()?;
+        let nclist = self.merge.nclist;
+        let range = nclist.intervals[block.start].start().clone()..nclist.intervals[last].end().clone();
+
+        //the top-level elements and everything nested in them
+        let mut items = Vec::new();
+        let mut stack = vec![(block.start, block.end)];
+        while let Some((start, end)) = stack.pop() {
+            for i in start..end {
+                items.push(&nclist.intervals[i]);
+                if let Some(sublist) = nclist.contained[i + 1] {
+                    stack.push(sublist);
+                }
+            }
+        }
+        Some((range, items))
+    }
+}
+
+fn is_empty<T>(e: &T) -> bool where T: Interval {
+    e.start() == e.end()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::Site;
+    use crate::NClist;
+
+    #[test]
+    fn merge() {
+        let nclist = NClist::from_vec(vec![(1u32..5), (2..3), (4..8), (8..10), (12..20), (13..14), (25..30)]).unwrap();
+        let merged: Vec<_> = nclist.merge().collect();
+        assert_eq!(merged, vec![1..10, 12..20, 25..30]);
+
+        let merged: Vec<_> = nclist.merge_with_gap(2).collect();
+        assert_eq!(merged, vec![1..20, 25..30]);
+        let merged: Vec<_> = nclist.merge_with_gap(5).collect();
+        assert_eq!(merged, vec![1..30]);
+
+        let empty: NClist<std::ops::Range<u32>> = NClist::from_vec(Vec::new()).unwrap();
+        assert_eq!(empty.merge().count(), 0);
+    }
+
+    #[test]
+    fn merge_with_items() {
+        let nclist = NClist::from_vec(vec![(1u32..5), (2..3), (4..8), (12..20), (13..14), (14..15)]).unwrap();
+        let mut blocks: Vec<_> = nclist.merge().with_items().collect();
+        for (_, items) in blocks.iter_mut() {
+            items.sort_by_key(|r| r.start);
+        }
+        assert_eq!(blocks, vec![
+            (1..8, vec![&(1..5), &(2..3), &(4..8)]),
+            (12..20, vec![&(12..20), &(13..14), &(14..15)]),
+        ]);
+    }
+
+    #[test]
+    fn empty_elements() {
+        let nclist = NClist::from_vec(vec![Site(0..2), Site(2..2), Site(2..4), Site(5..5), Site(8..9), Site(12..12)]).unwrap();
+        let merged: Vec<_> = nclist.merge().collect();
+        assert_eq!(merged, vec![0..4, 8..9]);
+        let merged: Vec<_> = nclist.merge_with_gap(1).collect();
+        assert_eq!(merged, vec![0..4, 8..9]);
+        let merged: Vec<_> = nclist.merge_with_gap(4).collect();
+        assert_eq!(merged, vec![0..9]);
+
+        let mut blocks: Vec<_> = nclist.merge().with_items().collect();
+        for (_, items) in blocks.iter_mut() {
+            items.sort_by_key(|s| (s.0.start, s.0.end));
+        }
+        assert_eq!(blocks, vec![
+            (0..4, vec![&Site(0..2), &Site(2..2), &Site(2..4)]),
+            (8..9, vec![&Site(8..9)]),
+        ]);
+
+        let only_empty = NClist::from_vec(vec![Site(3..3)]).unwrap();
+        assert_eq!(only_empty.merge().count(), 0);
+    }
+}
@@ -0,0 +1,229 @@
+//! Nearest neighbour queries on an `NClist<T>`.
+//!
+//! Within a sublist the start and end coordinates are both sorted, so the closest interval in a
+//! sublist is found with a binary search. Only the sublists of intervals that span the query
+//! coordinate can contain a closer interval, all other sublists are skipped.
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+use std::ops::{Range, Sub};
+
+use crate::query;
+use crate::{Interval, NClist};
+
+impl<T> NClist<T> where T: Interval {
+    /// Returns the elements that end closest before coordinate `p`, i.e. with the largest end
+    /// coordinate of the elements that do not contain `p` or any coordinate after it (see
+    /// `Interval::SEMANTICS`). All elements with this end coordinate are returned.
+    pub fn preceding(&self, p: &T::Coord) -> Vec<&T> {
+        self.last_ending(p, false)
+    }
+
+    /// Returns the elements that start closest after coordinate `p`, i.e. with the smallest start
+    /// coordinate for which `start > p`. All elements with this start coordinate are returned.
+    pub fn following(&self, p: &T::Coord) -> Vec<&T> {
+        self.first_starting(p, false)
+    }
+
+    /// Returns the elements closest to the `Range` r. When elements overlap `r` these are
+    /// returned. Otherwise the closest preceding and following elements are compared by their
+    /// distance to `r`, and all elements at the smallest distance are returned.
+    pub fn nearest(&self, r: &Range<T::Coord>) -> Vec<&T>
+        where T::Coord: Clone + Sub<Output = T::Coord>
+    {
+        self.nearest_k(r, 1)
+    }
+
+    /// Returns at least the `k` elements closest to the `Range` r, ordered by distance.
+    /// Overlapping elements have distance zero. When multiple elements are at the same distance as
+    /// the k-th element they are all returned. Fewer elements are returned if the `NClist`
+    /// contains fewer than `k` elements.
+    pub fn nearest_k(&self, r: &Range<T::Coord>, k: usize) -> Vec<&T>
+        where T::Coord: Clone + Sub<Output = T::Coord>
+    {
+        let mut result = self.collect_overlaps(r);
+
+        let mut before = self.last_ending(&r.start, false);
+        let mut after = self.first_starting(&r.end, true);
+        while result.len() < k && !(before.is_empty() && after.is_empty()) {
+            let d_before = before.first().map(|e| r.start.clone() - e.end().clone());
+            let d_after = after.first().map(|e| e.start().clone() - r.end.clone());
+            let (take_before, take_after) = match (d_before, d_after) {
+                (Some(b), Some(a)) => (b <= a, a <= b),
+                (b, a) => (b.is_some(), a.is_some()),
+            };
+
+            if take_before {
+                let next = self.last_ending(before[0].end(), true);
+                result.append(&mut before);
+                before = next;
+            }
+            if take_after {
+                let next = self.first_starting(after[0].start(), false);
+                result.append(&mut after);
+                after = next;
+            }
+        }
+        result
+    }
+
+    /// All elements overlapping `r`. Unlike `overlaps` the result does not borrow `r`.
+    fn collect_overlaps(&self, r: &Range<T::Coord>) -> Vec<&T> {
+        let mut result = Vec::new();
+        if r.end <= r.start {
+            return result;
+        }
+        let mut queue = VecDeque::new();
+        queue.push_back(self.contained[0].unwrap());
+        while let Some((start, end)) = queue.pop_front() {
+            let mut pos = start + self.bin_search_end(start, end, &r.start);
+            while pos < end && *self.intervals[pos].start() < r.end {
+                result.push(&self.intervals[pos]);
+                pos += 1;
+                if let Some(sublist) = self.contained[pos] {
+                    queue.push_back(sublist);
+                }
+            }
+        }
+        result
+    }
+
+    /// The elements with the largest end coordinate that end before `p` (`query::ends_before`),
+    /// or with `end < p` when `strict`.
+    fn last_ending(&self, p: &T::Coord, strict: bool) -> Vec<&T> {
+        let mut best: Vec<&T> = Vec::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(self.contained[0].unwrap());
+        while let Some((start, end)) = queue.pop_front() {
+            let list = &self.intervals[start..end];
+            let n_before = list.partition_point(|e| if strict { e.end() < p } else { query::ends_before(e, p) });
+            let n_spanning = list.partition_point(|e| e.start() < p);
+
+            //elements that span p can contain elements ending before p
+            for i in n_before..n_spanning.max(n_before) {
+                if let Some(sublist) = self.contained[start + i + 1] {
+                    queue.push_back(sublist);
+                }
+            }
+
+            if let Some(last) = list[..n_before].last() {
+                match best.first().map(|b| last.end().cmp(b.end())) {
+                    Some(Ordering::Less) => continue,
+                    Some(Ordering::Greater) => best.clear(),
+                    _ => {}
+                }
+                best.extend(list[..n_before].iter().rev().take_while(|e| e.end() == last.end()));
+            }
+        }
+        best
+    }
+
+    /// The elements with the smallest start coordinate `>= p` (`inclusive`) or `> p`.
+    fn first_starting(&self, p: &T::Coord, inclusive: bool) -> Vec<&T> {
+        let mut best: Vec<&T> = Vec::new();
+        let mut queue = VecDeque::new();
+        queue.push_back(self.contained[0].unwrap());
+        while let Some((start, end)) = queue.pop_front() {
+            let list = &self.intervals[start..end];
+            //closed elements ending at p can contain elements starting at p
+            let n_ending = list.partition_point(|e| e.end() < p);
+            let n_before = list.partition_point(|e| if inclusive { e.start() < p } else { e.start() <= p });
+
+            //elements that span p can contain elements starting after p
+            for i in n_ending..n_before.max(n_ending) {
+                if let Some(sublist) = self.contained[start + i + 1] {
+                    queue.push_back(sublist);
+                }
+            }
+
+            if let Some(first) = list.get(n_before) {
+                match best.first().map(|b| first.start().cmp(b.start())) {
+                    Some(Ordering::Greater) => continue,
+                    Some(Ordering::Less) => best.clear(),
+                    _ => {}
+                }
+                //contained elements can start at the same coordinate
+                for (i, e) in list[n_before..].iter().enumerate().take_while(|(_, e)| e.start() == first.start()) {
+                    best.push(e);
+                    if let Some(sublist) = self.contained[start + n_before + i + 1] {
+                        queue.push_back(sublist);
+                    }
+                }
+            }
+        }
+        best
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::{random_intervals, Site};
+    use crate::NClist;
+    use std::ops::Range;
+
+    fn sorted(v: Vec<&Range<u32>>) -> Vec<Range<u32>> {
+        let mut v: Vec<_> = v.into_iter().cloned().collect();
+        v.sort_by(|a, b| a.start.cmp(&b.start).then(b.end.cmp(&a.end)));
+        v
+    }
+
+    #[test]
+    fn preceding_following() {
+        let nclist = NClist::from_vec(vec![(0..100), (10..20), (12..15), (15..30), (18..19), (40..50), (40..45), (60..70)]).unwrap();
+        assert_eq!(sorted(nclist.preceding(&16)), vec![12..15]);
+        assert_eq!(sorted(nclist.preceding(&19)), vec![18..19]);
+        assert_eq!(sorted(nclist.preceding(&55)), vec![40..50]);
+        assert_eq!(sorted(nclist.preceding(&5)), vec![]);
+        assert_eq!(sorted(nclist.following(&16)), vec![18..19]);
+        assert_eq!(sorted(nclist.following(&35)), vec![40..50, 40..45]);
+        assert_eq!(sorted(nclist.following(&60)), vec![]);
+        assert_eq!(sorted(nclist.following(&59)), vec![60..70]);
+    }
+
+    #[test]
+    fn nearest() {
+        let nclist = NClist::from_vec(vec![(10..20), (12..15), (30..40), (50..52), (54..60)]).unwrap();
+        assert_eq!(sorted(nclist.nearest(&(14..16))), vec![10..20, 12..15]);
+        assert_eq!(sorted(nclist.nearest(&(22..24))), vec![10..20]);
+        assert_eq!(sorted(nclist.nearest(&(25..28))), vec![30..40]);
+        assert_eq!(sorted(nclist.nearest(&(22..28))), vec![10..20, 30..40]);
+        assert_eq!(sorted(nclist.nearest(&(52..54))), vec![50..52, 54..60]);
+        assert_eq!(sorted(nclist.nearest(&(100..101))), vec![54..60]);
+
+        assert_eq!(sorted(nclist.nearest_k(&(42..43), 2)), vec![30..40, 50..52]);
+        assert_eq!(sorted(nclist.nearest_k(&(42..43), 3)), vec![30..40, 50..52, 54..60]);
+        assert_eq!(nclist.nearest_k(&(42..43), 10).len(), 5);
+
+        let empty: NClist<Range<u32>> = NClist::from_vec(Vec::new()).unwrap();
+        assert!(empty.nearest(&(1..2)).is_empty());
+    }
+
+    #[test]
+    fn closed_and_empty() {
+        let nclist = NClist::from_vec(vec![(1u32..=5), (8..=9)]).unwrap();
+        assert_eq!(nclist.nearest_k(&(5..6), 2), vec![&(1..=5), &(8..=9)]);
+        assert_eq!(nclist.preceding(&5), Vec::<&std::ops::RangeInclusive<u32>>::new());
+        assert_eq!(nclist.preceding(&6), vec![&(1..=5)]);
+        let nested = NClist::from_vec(vec![(3u32..=5), (5..=5)]).unwrap();
+        assert_eq!(nested.nearest_k(&(1..5), 2), vec![&(3..=5), &(5..=5)]);
+
+        let sites = NClist::from_vec(vec![Site(5..5), Site(8..9)]).unwrap();
+        assert_eq!(sites.nearest_k(&(5..6), 2), vec![&Site(5..5), &Site(8..9)]);
+        assert_eq!(sites.preceding(&5), Vec::<&Site>::new());
+        assert_eq!(sites.preceding(&6), vec![&Site(5..5)]);
+    }
+
+    #[test]
+    fn matches_linear_scan() {
+        let v = random_intervals(300, 500, 40);
+        let nclist = NClist::from_vec(v.clone()).unwrap();
+        for p in 0..560 {
+            let max_end = v.iter().filter(|e| e.end <= p).map(|e| e.end).max();
+            let expected: Vec<_> = v.iter().filter(|e| Some(e.end) == max_end).collect();
+            assert_eq!(sorted(nclist.preceding(&p)), sorted(expected));
+
+            let min_start = v.iter().filter(|e| e.start > p).map(|e| e.start).min();
+            let expected: Vec<_> = v.iter().filter(|e| Some(e.start) == min_start).collect();
+            assert_eq!(sorted(nclist.following(&p)), sorted(expected));
+        }
+    }
+}
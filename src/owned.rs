@@ -0,0 +1,97 @@
+//! Overlap queries that own the query.
+//!
+//! `Overlaps` and `OrderedOverlaps` borrow the query, so it has to outlive the iterator. The
+//! iterators in this module store the query themselves. They can be returned from a function or
+//! created inside a `flat_map` closure over computed ranges.
+use crate::{Interval, NClist, OrderedState, OverlapsState, Query};
+
+/// Iterator over the elements overlapping an owned query, created by `NClist::overlaps_owned`.
+pub struct OwnedOverlaps<'a, T, Q> where T: 'a + Interval {
+    nclist: &'a NClist<T>,
+    query: Q,
+    state: OverlapsState,
+}
+
+/// Iterator over the elements overlapping an owned query ordered by start coordinate, created by
+/// `NClist::overlaps_ordered_owned`.
+pub struct OwnedOrderedOverlaps<'a, T, Q> where T: 'a + Interval {
+    nclist: &'a NClist<T>,
+    query: Q,
+    state: OrderedState,
+}
+
+impl<T> NClist<T> where T: Interval {
+    /// Returns an iterator that returns overlapping elements to query `q` like `overlaps`, but
+    /// takes ownership of the query.
+    pub fn overlaps_owned<Q>(&self, q: Q) -> OwnedOverlaps<'_, T, Q> where Q: Query<T::Coord> {
+        let state = OverlapsState::new(self, q.lower(), q.upper());
+        OwnedOverlaps { nclist: self, query: q, state }
+    }
+
+    /// Returns an iterator that returns overlapping elements to query `q` ordered by start
+    /// coordinate like `overlaps_ordered`, but takes ownership of the query.
+    pub fn overlaps_ordered_owned<Q>(&self, q: Q) -> OwnedOrderedOverlaps<'_, T, Q> where Q: Query<T::Coord> {
+        let state = OrderedState::new(self, q.lower(), q.upper());
+        OwnedOrderedOverlaps { nclist: self, query: q, state }
+    }
+}
+
+impl<'a, T, Q> OwnedOverlaps<'a, T, Q> where T: Interval {
+    /// Returns the query of this iterator.
+    pub fn query(&self) -> &Q {
+        &self.query
+    }
+}
+
+impl<'a, T, Q> OwnedOrderedOverlaps<'a, T, Q> where T: Interval {
+    /// Returns the query of this iterator.
+    pub fn query(&self) -> &Q {
+        &self.query
+    }
+}
+
+impl<'a, T, Q> Iterator for OwnedOverlaps<'a, T, Q> where T: Interval, Q: Query<T::Coord> {
+    type Item = &'a T;
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let nclist = self.nclist;
+        self.state.next_index(nclist, self.query.lower(), self.query.upper())
+            .map(|i| &nclist.intervals[i])
+    }
+}
+
+impl<'a, T, Q> Iterator for OwnedOrderedOverlaps<'a, T, Q> where T: Interval, Q: Query<T::Coord> {
+    type Item = &'a T;
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let nclist = self.nclist;
+        self.state.next_index(nclist, self.query.lower(), self.query.upper())
+            .map(|i| &nclist.intervals[i])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{NClist, OwnedOverlaps};
+    use std::ops::Range;
+
+    fn around(nclist: &NClist<Range<u32>>, p: u32) -> OwnedOverlaps<'_, Range<u32>, Range<u32>> {
+        nclist.overlaps_owned(p.saturating_sub(2)..p + 2)
+    }
+
+    #[test]
+    fn owned_queries() {
+        let nclist = NClist::from_vec(vec![(10u32..15), (10..20), (1..8), (12..13), (30..40)]).unwrap();
+        let it = around(&nclist, 11);
+        assert_eq!(it.query(), &(9..13));
+        assert_eq!(it.count(), 3);
+
+        let starts: Vec<u32> = [0u32, 10, 35].iter()
+            .flat_map(|&s| nclist.overlaps_ordered_owned(s..s + 3))
+            .map(|r| r.start)
+            .collect();
+        assert_eq!(starts, vec![1, 10, 10, 12, 30]);
+        assert_eq!(nclist.overlaps_owned(20..).count(), 1);
+        assert_eq!(nclist.overlaps_ordered_owned(15..15).count(), 0);
+    }
+}
@@ -0,0 +1,151 @@
+//! Parallel construction and batch queries of an `NClist`, enabled with the `rayon` feature.
+//!
+//! After sorting, every top-level interval and the intervals contained in it form a contiguous
+//! run that can be nested independently. The nested runs are built concurrently and stitched
+//! together: the top-level list is followed by the storage of every run, with the sublist
+//! positions of a run shifted by its offset. Only the top level is split, so deeply nested input
+//! does not cause deep recursion.
+//!
+//! Batch queries are answered independently on different threads, with at least
+//! `MIN_CHUNK_LEN` consecutive queries per task.
+use std::ops::Range;
+
+use rayon::prelude::*;
+
+use crate::{sort_order, Error, Interval, NClist};
+
+/// Runs with fewer intervals are nested sequentially.
+const MIN_PARALLEL_LEN: usize = 1 << 14;
+
+/// The minimum number of queries answered by a single task.
+const MIN_CHUNK_LEN: usize = 256;
+
+impl<T> NClist<T> where T: Interval + Send {
+    /// Create an `NClist` from `v` like `from_vec`, but sort and nest the intervals using all
+    /// threads of the rayon thread pool. The layout of the result can differ from `from_vec`,
+    /// queries return the same elements.
+    pub fn par_from_vec(v: Vec<T>) -> Result<NClist<T>, Error<T>> {
+        let mut v = Error::check(v, |e| e)?;
+        v.par_sort_by(sort_order);
+        Ok(par_nest(v, MIN_PARALLEL_LEN))
+    }
+}
+
+impl<T> NClist<T> where T: Interval + Sync, T::Coord: Sync {
+    /// Count the number of elements overlapping every query in `queries` using all threads of
+    /// the rayon thread pool. The counts are returned in the order of the queries.
+    pub fn par_count_overlaps(&self, queries: &[Range<T::Coord>]) -> Vec<usize> {
+        queries.par_iter()
+            .with_min_len(MIN_CHUNK_LEN)
+            .map(|q| self.count_overlaps(q))
+            .collect()
+    }
+
+    /// Returns the overlapping elements for every query in `queries` using all threads of the
+    /// rayon thread pool. The results are returned in the order of the queries.
+    pub fn par_overlaps(&self, queries: &[Range<T::Coord>]) -> Vec<Vec<&T>> {
+        queries.par_iter()
+            .with_min_len(MIN_CHUNK_LEN)
+            .map(|q| {
+                //the positions are not tied to the lifetime of the query
+                let mut it = self.overlaps(q);
+                std::iter::from_fn(|| it.next_index()).map(|i| &self.intervals[i]).collect()
+            })
+            .collect()
+    }
+}
+
+/// Nest the sorted intervals in `v`, nesting the runs below the top-level intervals concurrently
+/// when `v` has at least `min_len` intervals.
+fn par_nest<T>(v: Vec<T>, min_len: usize) -> NClist<T> where T: Interval + Send {
+    if v.len() < min_len {
+        return NClist::nest(v);
+    }
+
+    let mut top = Vec::new();
+    let mut runs = Vec::new();
+    let mut it = v.into_iter().peekable();
+    while let Some(e) = it.next() {
+        let mut contained = Vec::new();
+        while let Some(n) = it.next_if(|n| n.end() < e.end()) {
+            contained.push(n);
+        }
+        top.push(e);
+        runs.push(contained);
+    }
+    //release the storage of `v` before the runs are nested
+    drop(it);
+    let runs: Vec<NClist<T>> = runs.into_par_iter().map(NClist::nest).collect();
+
+    let mut list = NClist { contained: vec![None; top.len() + 1], intervals: top };
+    list.contained[0] = Some((0, list.intervals.len()));
+    for (i, run) in runs.into_iter().enumerate() {
+        if run.is_empty() {
+            continue;
+        }
+        let base = list.intervals.len();
+        //the top-level list of the run is the sublist of top-level interval i
+        let mut sublists = run.contained.iter().map(|c| c.map(|(start, end)| (start + base, end + base)));
+        list.contained[i + 1] = sublists.next().unwrap();
+        list.contained.extend(sublists);
+        list.intervals.extend(run.intervals);
+    }
+    list
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{par_nest, MIN_PARALLEL_LEN};
+    use crate::tests::random_intervals;
+    use crate::{layout, sort_order, NClist};
+    use std::ops::Range;
+
+    #[test]
+    fn parallel_build() {
+        let v = random_intervals(3000, 1000, 200);
+        let nclist = NClist::from_vec(v.clone()).unwrap();
+
+        let mut sorted = v.clone();
+        sorted.sort_by(sort_order);
+        for min_len in [1, 10, 100, 10000] {
+            let par = par_nest(sorted.clone(), min_len);
+            assert_eq!(layout::check(&par, &par.intervals, par.contained.len()), Ok(()));
+            assert!(par.iter().eq(nclist.iter()));
+            for q in (0..1250).step_by(9).map(|s| s..s + 1 + s % 40) {
+                assert_eq!(par.count_overlaps(&q), nclist.count_overlaps(&q));
+            }
+        }
+
+        let par = NClist::par_from_vec(v).unwrap();
+        assert_eq!(par.count_overlaps(&(100..200)), nclist.count_overlaps(&(100..200)));
+        assert!(NClist::par_from_vec(vec![(1u32..2), (5..5)]).is_err());
+    }
+
+    #[test]
+    fn deeply_nested() {
+        let n = MIN_PARALLEL_LEN as u32 + 1000;
+        let chain: Vec<Range<u32>> = (0..n).map(|i| i..2 * n - i).collect();
+        let par = NClist::par_from_vec(chain).unwrap();
+        assert_eq!(layout::check(&par, &par.intervals, par.contained.len()), Ok(()));
+        assert_eq!(par.len(), n as usize);
+        assert_eq!(par.count_overlaps(&(n - 1..n)), n as usize);
+        assert_eq!(par.count_stab(&10), 11);
+    }
+
+    #[test]
+    fn parallel_queries() {
+        let v = random_intervals(3000, 1000, 200);
+        let nclist = NClist::from_vec(v).unwrap();
+        let queries = random_intervals(5000, 1250, 40);
+
+        let counts = nclist.par_count_overlaps(&queries);
+        let results = nclist.par_overlaps(&queries);
+        assert_eq!(counts.len(), queries.len());
+        assert_eq!(results.len(), queries.len());
+        for ((q, count), result) in queries.iter().zip(counts).zip(results) {
+            assert_eq!(count, nclist.count_overlaps(q));
+            assert!(result.into_iter().eq(nclist.overlaps(q)));
+        }
+        assert!(nclist.par_count_overlaps(&[]).is_empty());
+    }
+}
@@ -0,0 +1,180 @@
+//! Query types accepted by the overlap queries.
+//!
+//! A query is described by an inclusive start coordinate and an inclusive or exclusive end
+//! coordinate, either of which can be unbounded. Every `Interval` is a query, as are the range
+//! types from `std::ops`, so a query can be made without copying coordinates into a `Range`.
+use std::ops::{Bound, RangeFrom, RangeFull, RangeTo, RangeToInclusive};
+
+use crate::{Interval, Semantics};
+
+/// A query for the elements of an `NClist` with coordinate type `C`. Elements overlap the query
+/// when they end after `lower` and start before (or at, for an inclusive bound) `upper`.
+pub trait Query<C> {
+    /// The inclusive start coordinate of the query, `None` when unbounded.
+    fn lower(&self) -> Option<&C>;
+
+    /// The end coordinate of the query.
+    fn upper(&self) -> Bound<&C>;
+
+    /// Returns `true` if the query contains no coordinates. Empty queries overlap nothing.
+    fn is_empty(&self) -> bool where C: Ord {
+        is_empty(self.lower(), self.upper())
+    }
+}
+
+/// Every interval, e.g. a `Range` or a record from a BED file, can be used as a query.
+impl<I> Query<I::Coord> for I where I: Interval {
+    #[inline(always)]
+    fn lower(&self) -> Option<&I::Coord> {
+        Some(self.start())
+    }
+
+    #[inline(always)]
+    fn upper(&self) -> Bound<&I::Coord> {
+        match I::SEMANTICS {
+            Semantics::HalfOpen => Bound::Excluded(self.end()),
+            Semantics::Closed => Bound::Included(self.end()),
+            //an empty interval is a query for a single position
+            Semantics::AllowEmpty if self.end() == self.start() => Bound::Included(self.end()),
+            Semantics::AllowEmpty => Bound::Excluded(self.end()),
+        }
+    }
+}
+
+impl<C> Query<C> for RangeFrom<C> {
+    fn lower(&self) -> Option<&C> {
+        Some(&self.start)
+    }
+
+    fn upper(&self) -> Bound<&C> {
+        Bound::Unbounded
+    }
+}
+
+impl<C> Query<C> for RangeTo<C> {
+    fn lower(&self) -> Option<&C> {
+        None
+    }
+
+    fn upper(&self) -> Bound<&C> {
+        Bound::Excluded(&self.end)
+    }
+}
+
+impl<C> Query<C> for RangeToInclusive<C> {
+    fn lower(&self) -> Option<&C> {
+        None
+    }
+
+    fn upper(&self) -> Bound<&C> {
+        Bound::Included(&self.end)
+    }
+}
+
+impl<C> Query<C> for RangeFull {
+    fn lower(&self) -> Option<&C> {
+        None
+    }
+
+    fn upper(&self) -> Bound<&C> {
+        Bound::Unbounded
+    }
+}
+
+/// Returns `true` if the coordinates of `e` are valid for its semantics.
+pub(crate) fn is_valid<T: Interval>(e: &T) -> bool {
+    match T::SEMANTICS {
+        Semantics::HalfOpen => e.end() > e.start(),
+        Semantics::Closed | Semantics::AllowEmpty => e.end() >= e.start(),
+    }
+}
+
+/// Returns `true` if element `e` ends before a query starting at `q`, i.e. does not contain `q`
+/// or any coordinate after it.
+#[inline]
+pub(crate) fn ends_before<T: Interval>(e: &T, q: &T::Coord) -> bool {
+    match T::SEMANTICS {
+        Semantics::HalfOpen => e.end() <= q,
+        Semantics::Closed => e.end() < q,
+        //an empty element at q is position q
+        Semantics::AllowEmpty => e.end() < q || (e.end() == q && e.start() < e.end()),
+    }
+}
+
+/// Returns `true` if an element starting at `start` starts before the query end `end`.
+#[inline]
+pub(crate) fn starts_before<C: Ord>(start: &C, end: Bound<&C>) -> bool {
+    match end {
+        Bound::Included(end) => start <= end,
+        Bound::Excluded(end) => start < end,
+        Bound::Unbounded => true,
+    }
+}
+
+/// Returns `true` if every coordinate before end bound `a` is also before end bound `b`.
+#[inline]
+pub(crate) fn ends_within<C: Ord>(a: Bound<&C>, b: Bound<&C>) -> bool {
+    match (a, b) {
+        (_, Bound::Unbounded) => true,
+        (Bound::Unbounded, _) => false,
+        (Bound::Included(a), Bound::Excluded(b)) => a < b,
+        (Bound::Included(a), Bound::Included(b))
+            | (Bound::Excluded(a), Bound::Included(b))
+            | (Bound::Excluded(a), Bound::Excluded(b)) => a <= b,
+    }
+}
+
+/// Returns `true` if the query `start` to `end` contains no coordinates.
+#[inline]
+pub(crate) fn is_empty<C: Ord>(start: Option<&C>, end: Bound<&C>) -> bool {
+    match start {
+        Some(start) => !starts_before(start, end),
+        None => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::NClist;
+    use std::ops::Range;
+
+    #[test]
+    fn range_queries() {
+        let nclist = NClist::from_vec(vec![(10u32..15), (10..20), (1..8), (12..13), (30..40)]).unwrap();
+        assert_eq!(nclist.count_overlaps(&(8..=10)), 2);
+        assert_eq!(nclist.count_overlaps(&(8..10)), 0);
+        assert_eq!(nclist.count_overlaps(&(12..)), 4);
+        assert_eq!(nclist.count_overlaps(&(..10)), 1);
+        assert_eq!(nclist.count_overlaps(&(..=10)), 3);
+        assert_eq!(nclist.count_overlaps(&(..)), 5);
+        #[allow(clippy::reversed_empty_ranges)]
+        let empty = 10..=9;
+        assert_eq!(nclist.count_overlaps(&empty), 0);
+
+        let found: Vec<_> = nclist.overlaps_ordered(&(..=12)).cloned().collect();
+        assert_eq!(found, vec![1..8, 10..20, 10..15, 12..13]);
+        let found: Vec<_> = nclist.overlaps(&(20..)).cloned().collect();
+        assert_eq!(found, vec![30..40]);
+    }
+
+    #[test]
+    fn interval_queries() {
+        struct Peak(Range<u32>);
+
+        impl crate::Interval for Peak {
+            type Coord = u32;
+            fn start(&self) -> &u32 {
+                &self.0.start
+            }
+            fn end(&self) -> &u32 {
+                &self.0.end
+            }
+        }
+
+        let nclist = NClist::from_vec(vec![(10u32..15), (10..20), (1..8), (12..13), (30..40)]).unwrap();
+        let peak = Peak(14..31);
+        assert_eq!(nclist.count_overlaps(&peak), 3);
+        assert_eq!(nclist.overlaps(&peak).count(), 3);
+        assert_eq!(nclist.overlaps_ordered(&peak).count(), 3);
+    }
+}
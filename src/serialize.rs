@@ -0,0 +1,60 @@
+//! Serialization of an `NClist` with `serde`, enabled with the `serde` feature.
+//!
+//! The sorted and nested layout is stored, so a deserialized `NClist` does not have to be
+//! rebuilt. The layout is validated when it is deserialized.
+use serde::de::Error as _;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{layout, Interval, NClist};
+
+impl<T> Serialize for NClist<T> where T: Interval + Serialize {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut s = serializer.serialize_struct("NClist", 2)?;
+        s.serialize_field("intervals", &self.intervals)?;
+        s.serialize_field("contained", &self.contained)?;
+        s.end()
+    }
+}
+
+impl<'de, T> Deserialize<'de> for NClist<T> where T: Interval + Deserialize<'de> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(rename = "NClist")]
+        struct Stored<T> {
+            intervals: Vec<T>,
+            contained: Vec<Option<(usize, usize)>>,
+        }
+
+        let Stored { intervals, contained } = Stored::deserialize(deserializer)?;
+        let nclist = NClist { intervals, contained };
+        layout::check(&nclist, &nclist.intervals, nclist.contained.len()).map_err(D::Error::custom)?;
+        Ok(nclist)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::NClist;
+    use std::ops::Range;
+
+    #[test]
+    fn roundtrip() {
+        let nclist = NClist::from_vec(vec![(10u32..15), (10..20), (1..8), (12..13), (30..40)]).unwrap();
+        let json = serde_json::to_string(&nclist).unwrap();
+        let loaded: NClist<Range<u32>> = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.intervals, nclist.intervals);
+        assert_eq!(loaded.contained, nclist.contained);
+        assert_eq!(loaded.count_overlaps(&(11..13)), 3);
+    }
+
+    #[test]
+    fn invalid_layout() {
+        let json = r#"{"intervals":[{"start":10,"end":20},{"start":1,"end":8}],"contained":[[0,2],null,null]}"#;
+        assert!(serde_json::from_str::<NClist<Range<u32>>>(json).is_err());
+        let json = r#"{"intervals":[{"start":1,"end":8},{"start":10,"end":20}],"contained":[[0,2],null,null]}"#;
+        assert!(serde_json::from_str::<NClist<Range<u32>>>(json).is_ok());
+        let json = r#"{"intervals":[{"start":1,"end":8}],"contained":[[0,1],[0,1]]}"#;
+        assert!(serde_json::from_str::<NClist<Range<u32>>>(json).is_err());
+    }
+}
@@ -0,0 +1,107 @@
+//! Set operations on the positions covered by two `NClist`s.
+//!
+//! Both lists are first merged into sorted non-overlapping ranges (see `NClist::merge`). The
+//! boundaries of these ranges are then visited in order and the output contains the positions
+//! where the set operation on "covered by self" and "covered by other" holds. The results are
+//! sorted non-overlapping ranges that can be used to create a new `NClist<Range<T::Coord>>`.
+use std::ops::Range;
+
+use crate::{Interval, NClist};
+
+impl<T> NClist<T> where T: Interval, T::Coord: Clone {
+    /// Returns the ranges covered by both this `NClist` and `other`.
+    pub fn intersect<U>(&self, other: &NClist<U>) -> Vec<Range<T::Coord>> where U: Interval<Coord = T::Coord> {
+        combine(self, other, |a, b| a && b)
+    }
+
+    /// Returns the ranges covered by this `NClist` or `other`.
+    pub fn union<U>(&self, other: &NClist<U>) -> Vec<Range<T::Coord>> where U: Interval<Coord = T::Coord> {
+        combine(self, other, |a, b| a || b)
+    }
+
+    /// Returns the ranges covered by this `NClist` but not by `other`.
+    pub fn subtract<U>(&self, other: &NClist<U>) -> Vec<Range<T::Coord>> where U: Interval<Coord = T::Coord> {
+        combine(self, other, |a, b| a && !b)
+    }
+
+    /// Returns the ranges covered by exactly one of this `NClist` and `other`.
+    pub fn symmetric_difference<U>(&self, other: &NClist<U>) -> Vec<Range<T::Coord>> where U: Interval<Coord = T::Coord> {
+        combine(self, other, |a, b| a != b)
+    }
+}
+
+fn combine<T, U, F>(a: &NClist<T>, b: &NClist<U>, op: F) -> Vec<Range<T::Coord>>
+    where T: Interval, U: Interval<Coord = T::Coord>, T::Coord: Clone, F: Fn(bool, bool) -> bool
+{
+    let a: Vec<_> = a.merge().collect();
+    let b: Vec<_> = b.merge().collect();
+    let (mut ia, mut ib) = (0, 0);
+    let (mut in_a, mut in_b) = (false, false);
+    let mut open: Option<T::Coord> = None;
+    let mut result = Vec::new();
+
+    loop {
+        //the next boundary of a range in a or b
+        let next_a = a.get(ia / 2).map(|r| if ia % 2 == 0 { &r.start } else { &r.end });
+        let next_b = b.get(ib / 2).map(|r| if ib % 2 == 0 { &r.start } else { &r.end });
+        let pos = match (next_a, next_b) {
+            (Some(x), Some(y)) => x.min(y),
+            (Some(x), None) => x,
+            (None, Some(y)) => y,
+            (None, None) => break,
+        }.clone();
+
+        if next_a == Some(&pos) {
+            in_a = !in_a;
+            ia += 1;
+        }
+        if next_b == Some(&pos) {
+            in_b = !in_b;
+            ib += 1;
+        }
+
+        match (open.take(), op(in_a, in_b)) {
+            (None, true) => open = Some(pos),
+            (Some(start), false) => result.push(start..pos),
+            (o, _) => open = o,
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::Site;
+    use crate::NClist;
+
+    #[test]
+    fn set_operations() {
+        let a = NClist::from_vec(vec![(0u32..10), (2..4), (20..30), (40..50)]).unwrap();
+        let b = NClist::from_vec(vec![(5u32..25), (30..35), (45..50), (60..70)]).unwrap();
+
+        assert_eq!(a.intersect(&b), vec![5..10, 20..25, 45..50]);
+        assert_eq!(a.union(&b), vec![0..35, 40..50, 60..70]);
+        assert_eq!(a.subtract(&b), vec![0..5, 25..30, 40..45]);
+        assert_eq!(b.subtract(&a), vec![10..20, 30..35, 60..70]);
+        assert_eq!(a.symmetric_difference(&b), vec![0..5, 10..20, 25..35, 40..45, 60..70]);
+
+        let empty: NClist<std::ops::Range<u32>> = NClist::from_vec(Vec::new()).unwrap();
+        assert_eq!(a.intersect(&empty), vec![]);
+        assert_eq!(a.subtract(&empty), vec![0..10, 20..30, 40..50]);
+
+        let result = NClist::from_vec(a.symmetric_difference(&b)).unwrap();
+        assert_eq!(result.count_overlaps(&(8..12)), 1);
+    }
+
+    #[test]
+    fn empty_elements() {
+        let a = NClist::from_vec(vec![Site(0..2), Site(5..5), Site(8..9)]).unwrap();
+        let b = NClist::from_vec(vec![Site(1..3), Site(9..9)]).unwrap();
+
+        assert_eq!(a.union(&b), vec![0..3, 8..9]);
+        assert_eq!(a.symmetric_difference(&b), vec![0..1, 2..3, 8..9]);
+        assert_eq!(a.intersect(&b), vec![1..2]);
+        assert!(NClist::from_vec(a.union(&b)).is_ok());
+        assert!(NClist::from_vec(a.symmetric_difference(&b)).is_ok());
+    }
+}
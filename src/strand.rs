@@ -0,0 +1,174 @@
+//! Strand-aware overlap queries.
+//!
+//! Genomic features are located on the forward or reverse strand of a sequence (or have no strand
+//! information). A `StrandedNClist<T>` keeps a separate `NClist<T>` for each strand, so queries
+//! restricted to the same or the opposite strand only visit the matching intervals.
+use std::iter::Flatten;
+
+use crate::{Error, Interval, NClist, Overlaps, Query};
+
+/// The strand of an interval.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Strand {
+    Forward,
+    Reverse,
+    Unknown,
+}
+
+/// The strand matching criterion for a query.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum StrandMode {
+    /// Return overlapping intervals on all strands.
+    Ignore,
+    /// Return overlapping intervals on the same strand as the query.
+    Same,
+    /// Return overlapping intervals on the opposite strand of the query. Intervals with an
+    /// `Unknown` strand are never on the opposite strand.
+    Opposite,
+}
+
+/// An interval located on a strand. This is required for creating a `StrandedNClist<T>`.
+pub trait Stranded: Interval {
+    /// Return the strand of the interval
+    fn strand(&self) -> Strand;
+}
+
+impl Strand {
+    /// Returns the opposite strand. The opposite of `Unknown` is `Unknown`.
+    pub fn opposite(self) -> Strand {
+        match self {
+            Strand::Forward => Strand::Reverse,
+            Strand::Reverse => Strand::Forward,
+            Strand::Unknown => Strand::Unknown,
+        }
+    }
+
+    fn index(self) -> usize {
+        match self {
+            Strand::Forward => 0,
+            Strand::Reverse => 1,
+            Strand::Unknown => 2,
+        }
+    }
+}
+
+/// A collection of three `NClist<T>`, one for every `Strand`.
+#[derive(Debug)]
+pub struct StrandedNClist<T> where T: Interval {
+    strands: [NClist<T>; 3],
+}
+
+pub struct StrandedOverlaps<'a, T> where T: 'a + Interval {
+    inner: Flatten<std::vec::IntoIter<Overlaps<'a, T>>>,
+}
+
+impl<T> StrandedNClist<T> where T: Stranded {
+    /// Create a `StrandedNClist` from a `Vec<T>`. The intervals are divided by strand and the
+    /// same validation as `NClist::from_vec` is applied.
+    pub fn from_vec(v: Vec<T>) -> Result<StrandedNClist<T>, Error<T>> {
+        let mut split = [Vec::new(), Vec::new(), Vec::new()];
+        for e in Error::check(v, |e| e)? {
+            split[e.strand().index()].push(e);
+        }
+        let [forward, reverse, unknown] = split;
+        Ok(StrandedNClist {
+            strands: [NClist::build(forward), NClist::build(reverse), NClist::build(unknown)]
+        })
+    }
+}
+
+impl<T> StrandedNClist<T> where T: Interval {
+    /// Returns the `NClist` containing the intervals on `strand`.
+    pub fn get(&self, strand: Strand) -> &NClist<T> {
+        &self.strands[strand.index()]
+    }
+
+    /// Returns the total number of intervals.
+    pub fn len(&self) -> usize {
+        self.strands.iter().map(NClist::len).sum()
+    }
+
+    /// Returns `true` if there are no intervals.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Count the number of elements overlapping query `r` that match `strand` according to
+    /// `mode`.
+    pub fn count_overlaps<Q>(&self, r: &Q, strand: Strand, mode: StrandMode) -> usize where Q: Query<T::Coord> + ?Sized {
+        self.lists(strand, mode).map(|nclist| nclist.count_overlaps(r)).sum()
+    }
+
+    /// Returns an iterator that returns elements overlapping query `r` that match `strand`
+    /// according to `mode`.
+    pub fn overlaps<'a, Q>(&'a self, r: &'a Q, strand: Strand, mode: StrandMode) -> StrandedOverlaps<'a, T> where Q: Query<T::Coord> + ?Sized {
+        let v: Vec<_> = self.lists(strand, mode).map(|nclist| nclist.overlaps(r)).collect();
+        StrandedOverlaps { inner: v.into_iter().flatten() }
+    }
+
+    fn lists(&self, strand: Strand, mode: StrandMode) -> impl Iterator<Item = &NClist<T>> {
+        let selected = match (mode, strand) {
+            (StrandMode::Ignore, _) => [true, true, true],
+            (StrandMode::Same, s) => [s == Strand::Forward, s == Strand::Reverse, s == Strand::Unknown],
+            (StrandMode::Opposite, s) => [s == Strand::Reverse, s == Strand::Forward, false],
+        };
+        self.strands.iter().enumerate().filter(move |(i, _)| selected[*i]).map(|(_, nclist)| nclist)
+    }
+}
+
+impl<'a, T> Iterator for StrandedOverlaps<'a, T> where T: Interval {
+    type Item = &'a T;
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ops::Range;
+
+    #[derive(Debug, PartialEq)]
+    struct Feature(Range<u32>, Strand);
+
+    impl Interval for Feature {
+        type Coord = u32;
+        fn start(&self) -> &u32 {
+            &self.0.start
+        }
+        fn end(&self) -> &u32 {
+            &self.0.end
+        }
+    }
+
+    impl Stranded for Feature {
+        fn strand(&self) -> Strand {
+            self.1
+        }
+    }
+
+    #[test]
+    fn stranded_queries() {
+        let v = vec![
+            Feature(10..20, Strand::Forward),
+            Feature(12..14, Strand::Forward),
+            Feature(5..15, Strand::Reverse),
+            Feature(1..30, Strand::Unknown),
+        ];
+        let nclist = StrandedNClist::from_vec(v).unwrap();
+        assert_eq!(nclist.len(), 4);
+
+        let q = 11..13;
+        assert_eq!(nclist.count_overlaps(&q, Strand::Forward, StrandMode::Ignore), 4);
+        assert_eq!(nclist.count_overlaps(&q, Strand::Forward, StrandMode::Same), 2);
+        assert_eq!(nclist.count_overlaps(&q, Strand::Forward, StrandMode::Opposite), 1);
+        assert_eq!(nclist.count_overlaps(&q, Strand::Unknown, StrandMode::Same), 1);
+        assert_eq!(nclist.count_overlaps(&q, Strand::Unknown, StrandMode::Opposite), 0);
+
+        let v: Vec<_> = nclist.overlaps(&q, Strand::Reverse, StrandMode::Opposite).collect();
+        assert_eq!(v, vec![&Feature(10..20, Strand::Forward), &Feature(12..14, Strand::Forward)]);
+        assert_eq!(nclist.overlaps(&(0..4), Strand::Forward, StrandMode::Ignore).count(), 1);
+        assert_eq!(nclist.get(Strand::Reverse).len(), 1);
+    }
+}